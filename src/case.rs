@@ -0,0 +1,186 @@
+use std::str::FromStr;
+
+use icu_casemap::options::TitlecaseOptions;
+use icu_casemap::{CaseMapper, TitlecaseMapper};
+use icu_locale_core::LanguageIdentifier;
+use serde::Deserialize;
+use unicode_segmentation::UnicodeSegmentation;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaseMode {
+    Upper,
+    Lower,
+    Title,
+    Snake,
+    Camel,
+    Pascal,
+    Kebab,
+    ScreamingSnake,
+    Slug,
+}
+
+impl FromStr for CaseMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().replace('-', "_").as_str() {
+            "upper" => Ok(Self::Upper),
+            "lower" => Ok(Self::Lower),
+            "title" => Ok(Self::Title),
+            "snake" | "snake_case" => Ok(Self::Snake),
+            "camel" | "camelcase" => Ok(Self::Camel),
+            "pascal" | "pascalcase" => Ok(Self::Pascal),
+            "kebab" | "kebab_case" => Ok(Self::Kebab),
+            "screaming_snake" | "screaming_snake_case" | "constant" => Ok(Self::ScreamingSnake),
+            "slug" | "slugify" => Ok(Self::Slug),
+            _ => Err(format!(
+                "Unknown case mode: {s}. Expected one of: upper, lower, title, snake, camel, pascal, kebab, screaming_snake, slug"
+            )),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct CaseOptions {
+    /// A BCP 47 language tag, for language-sensitive rules such as the Turkish dotted `İ`
+    /// or the Lithuanian dot above. Defaults to the language-neutral rules.
+    pub lang: Option<String>,
+
+    /// Transliterates identifier styles into ASCII. Slugs are always ASCII.
+    #[serde(default)]
+    pub ascii: bool,
+}
+
+pub fn convert_case(src: &str, mode: CaseMode, options: &CaseOptions) -> Result<String, String> {
+    let lang = match &options.lang {
+        Some(lang) => LanguageIdentifier::try_from_str(lang)
+            .map_err(|e| format!("Invalid language tag: {lang}. {e}"))?,
+        None => LanguageIdentifier::UNKNOWN,
+    };
+
+    let case_mapper = CaseMapper::new();
+
+    let result = match mode {
+        CaseMode::Upper => case_mapper.uppercase_to_string(src, &lang).into_owned(),
+        CaseMode::Lower => case_mapper.lowercase_to_string(src, &lang).into_owned(),
+        CaseMode::Title => titlecase(src, &lang),
+        CaseMode::Slug => slugify(src),
+        CaseMode::Snake
+        | CaseMode::Camel
+        | CaseMode::Pascal
+        | CaseMode::Kebab
+        | CaseMode::ScreamingSnake => {
+            let words = if options.ascii {
+                split_identifier(&deunicode::deunicode(src))
+            } else {
+                split_identifier(src)
+            };
+
+            join_identifier(&words, mode, &lang)
+        }
+    };
+
+    Ok(result)
+}
+
+fn join_identifier(words: &[String], mode: CaseMode, lang: &LanguageIdentifier) -> String {
+    let case_mapper = CaseMapper::new();
+
+    let lower = |word: &str| case_mapper.lowercase_to_string(word, lang).into_owned();
+    let upper = |word: &str| case_mapper.uppercase_to_string(word, lang).into_owned();
+
+    // Only the first letter is uppercased, so acronyms become `Http` rather than `HTTP`.
+    let capitalize = |word: &str| {
+        let mut chars = word.chars();
+        let first = chars.next().map(String::from).unwrap_or_default();
+        upper(&first) + &lower(chars.as_str())
+    };
+
+    let separator = match mode {
+        CaseMode::Snake | CaseMode::ScreamingSnake => "_",
+        CaseMode::Kebab => "-",
+        _ => "",
+    };
+
+    words
+        .iter()
+        .enumerate()
+        .map(|(idx, word)| match mode {
+            CaseMode::ScreamingSnake => upper(word),
+            CaseMode::Pascal => capitalize(word),
+            CaseMode::Camel if idx > 0 => capitalize(word),
+            _ => lower(word),
+        })
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+/// Titlecases every word, leaving the text between words as it is.
+fn titlecase(src: &str, lang: &LanguageIdentifier) -> String {
+    let titlecase_mapper = TitlecaseMapper::new();
+
+    src.split_word_bounds()
+        .map(|segment| {
+            if segment.chars().any(char::is_alphanumeric) {
+                titlecase_mapper.titlecase_segment_to_string(
+                    segment,
+                    lang,
+                    TitlecaseOptions::default(),
+                )
+            } else {
+                segment.into()
+            }
+        })
+        .collect()
+}
+
+/// Splits an identifier into words along separators and case changes,
+/// so `parseHTTPResponse`, `parse_http_response` and `Parse HTTP Response` give the same words.
+fn split_identifier(src: &str) -> Vec<String> {
+    let chars: Vec<char> = src.chars().collect();
+    let mut words: Vec<String> = Vec::new();
+    let mut word = String::new();
+
+    for (idx, &c) in chars.iter().enumerate() {
+        if !c.is_alphanumeric() {
+            if !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            continue;
+        }
+
+        let prev = idx.checked_sub(1).map(|idx| chars[idx]);
+        let next = chars.get(idx + 1).copied();
+
+        // `aB` starts a word at `B`, and so does `ABc`, which ends an acronym.
+        let is_boundary = c.is_uppercase()
+            && prev.is_some_and(|prev| {
+                prev.is_lowercase()
+                    || prev.is_numeric()
+                    || (prev.is_uppercase() && next.is_some_and(char::is_lowercase))
+            });
+
+        if is_boundary && !word.is_empty() {
+            words.push(std::mem::take(&mut word));
+        }
+
+        word.push(c);
+    }
+
+    if !word.is_empty() {
+        words.push(word);
+    }
+
+    words
+}
+
+/// Transliterates into lowercase ASCII words joined with `-`.
+pub fn slugify(src: &str) -> String {
+    let ascii = deunicode::deunicode(src).to_lowercase();
+
+    ascii
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
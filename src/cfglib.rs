@@ -1,5 +1,6 @@
 use clap::ArgMatches;
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::env::current_exe;
 use std::ops::BitOr;
 use std::path::{Path, PathBuf};
@@ -56,9 +57,9 @@ fn parse_arg<T: FromStr>(
             Err(_) => {
                 // log::error!("Failed to parse the '{arg}' argument. Incorrect value was given: '{value}'");
                 // std::process::exit(1);
-                return Err(ConfigError::BadArgument(format!(
+                Err(ConfigError::BadArgument(format!(
                     "Failed to parse the '{arg}' argument. Incorrect value was given: '{value}'"
-                )));
+                )))
             }
         },
         None => {
@@ -81,6 +82,16 @@ pub struct Config {
 
     #[serde(default = "default_logger_config")]
     pub logger: LoggerConfig,
+
+    /// Stopword lists by ISO 639-3 language code.
+    #[serde(default = "default_stopwords_config")]
+    pub stopwords: HashMap<String, Vec<String>>,
+
+    #[serde(default = "default_similarity_config")]
+    pub similarity: SimilarityConfig,
+
+    #[serde(default = "default_dictionaries_config")]
+    pub dictionaries: DictionariesConfig,
 }
 
 impl Default for Config {
@@ -90,6 +101,9 @@ impl Default for Config {
             service: default_service_config(),
             cache: default_cache_config(),
             logger: default_logger_config(),
+            stopwords: default_stopwords_config(),
+            similarity: default_similarity_config(),
+            dictionaries: default_dictionaries_config(),
         }
     }
 }
@@ -228,6 +242,12 @@ impl Config {
                 logger: LoggerConfig {
                     log_level: parse_arg(arg_matches, "log_level", || base.logger.log_level)?,
                 },
+
+                stopwords: base.stopwords,
+
+                similarity: base.similarity,
+
+                dictionaries: base.dictionaries,
             }, // Self
         ) // Ok()
     } // fn
@@ -253,6 +273,33 @@ fn default_logger_config() -> LoggerConfig {
     LoggerConfig::default()
 }
 
+#[inline]
+fn default_similarity_config() -> SimilarityConfig {
+    SimilarityConfig::default()
+}
+
+#[inline]
+fn default_dictionaries_config() -> DictionariesConfig {
+    DictionariesConfig::default()
+}
+
+#[inline]
+fn default_stopwords_config() -> HashMap<String, Vec<String>> {
+    let english = [
+        "a", "about", "after", "all", "also", "an", "and", "any", "are", "as", "at", "be",
+        "because", "been", "but", "by", "can", "could", "did", "do", "does", "for", "from", "had",
+        "has", "have", "he", "her", "his", "how", "i", "if", "in", "into", "is", "it", "its", "me",
+        "my", "no", "not", "of", "on", "or", "our", "she", "so", "than", "that", "the", "their",
+        "them", "then", "there", "these", "they", "this", "those", "to", "up", "us", "was", "we",
+        "were", "what", "when", "which", "who", "will", "with", "would", "you", "your",
+    ];
+
+    HashMap::from([(
+        "eng".to_owned(),
+        english.iter().map(|&word| word.to_owned()).collect(),
+    )])
+}
+
 #[derive(Deserialize, Debug)]
 pub struct CommonConfig {
     #[serde(default = "default_common_fallback_encoding")]
@@ -419,3 +466,53 @@ impl Default for LoggerConfig {
 pub fn default_logger_level() -> String {
     "info".into()
 }
+
+#[derive(Deserialize, Debug)]
+pub struct SimilarityConfig {
+    /// The longest text, in characters, which the edit distances and fuzzy search accept.
+    /// Their time grows with the product of both lengths.
+    #[serde(default = "default_similarity_max_length")]
+    pub max_length: usize,
+}
+
+impl Default for SimilarityConfig {
+    fn default() -> Self {
+        Self {
+            max_length: default_similarity_max_length(),
+        }
+    }
+}
+
+#[inline]
+const fn default_similarity_max_length() -> usize {
+    10_000
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DictionariesConfig {
+    /// Enables uploading and deleting dictionaries with `Authorization: Bearer <admin_token>`.
+    /// Disabled when unset, empty or only whitespace.
+    pub admin_token: Option<String>,
+
+    #[serde(default = "default_dictionaries_max_upload_size")]
+    pub max_upload_size: usize,
+
+    /// Dictionary files by name, relative to the executable unless absolute.
+    #[serde(default)]
+    pub files: HashMap<String, String>,
+}
+
+impl Default for DictionariesConfig {
+    fn default() -> Self {
+        Self {
+            admin_token: None,
+            max_upload_size: default_dictionaries_max_upload_size(),
+            files: HashMap::new(),
+        }
+    }
+}
+
+#[inline]
+const fn default_dictionaries_max_upload_size() -> usize {
+    64 * 1024 * 1024
+}
@@ -0,0 +1,480 @@
+use serde::Deserialize;
+
+use crate::utils::DecodingError;
+
+const UU_LINE_LENGTH: usize = 45;
+
+const BINHEX_ALPHABET: &[u8; 64] =
+    b"!\"#$%&'()*+,-012345689@ABCDEFGHIJKLMNPQRSTUVXYZ[`abcdefhijklmpqr";
+const BINHEX_MARKER: &str = "(This file must be converted with BinHex";
+const BINHEX_RUN_LENGTH_MARKER: u8 = 0x90;
+
+#[derive(Deserialize, Debug, Default)]
+pub struct DecodeOptions {
+    /// Responds with the decoded bytes as they are, instead of decoding them into text.
+    #[serde(default)]
+    pub raw: bool,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct UuencodeOptions {
+    #[serde(default = "default_uuencode_name")]
+    pub name: String,
+
+    #[serde(default = "default_uuencode_mode")]
+    pub mode: String,
+}
+
+#[inline]
+fn default_uuencode_name() -> String {
+    "data".into()
+}
+
+#[inline]
+fn default_uuencode_mode() -> String {
+    "644".into()
+}
+
+/// A file extracted out of a uuencode, yEnc or binhex container.
+#[derive(Debug, Default)]
+pub struct DecodedFile {
+    pub name: Option<String>,
+    pub data: Vec<u8>,
+}
+
+#[inline]
+pub fn is_uuencoded(src: &str) -> bool {
+    src.lines().any(|line| parse_uu_begin_line(line).is_some())
+}
+
+#[inline]
+pub fn is_yencoded(src: &str) -> bool {
+    src.lines().any(|line| line.starts_with("=ybegin "))
+}
+
+#[inline]
+pub fn is_binhexed(src: &str) -> bool {
+    src.contains(BINHEX_MARKER)
+}
+
+/// Returns the mode and file name of a `begin 644 file.txt` line.
+fn parse_uu_begin_line(line: &str) -> Option<(&str, &str)> {
+    let rest = line.strip_prefix("begin ")?;
+    let (mode, name) = rest.split_once(' ')?;
+
+    is_uu_mode(mode).then(|| (mode, name.trim_end()))
+}
+
+/// A Unix file mode of 3 or 4 octal digits, e.g. `644` or `0755`.
+#[inline]
+fn is_uu_mode(mode: &str) -> bool {
+    (3..=4).contains(&mode.len()) && mode.chars().all(|c| c.is_digit(8))
+}
+
+/// Decodes uuencoded data. The `begin`/`end` lines are optional.
+pub fn decode_uuencode(src: &str) -> Result<DecodedFile, DecodingError> {
+    let mut result = DecodedFile::default();
+
+    let mut lines = src.lines();
+
+    // Skip anything that comes before the `begin` line, if there is one.
+    if is_uuencoded(src) {
+        for line in lines.by_ref() {
+            if let Some((_, name)) = parse_uu_begin_line(line) {
+                result.name = Some(name.to_owned());
+                break;
+            }
+        }
+    }
+
+    for line in lines {
+        let line = line.trim_end_matches('\r');
+
+        if line == "end" {
+            break;
+        }
+
+        let Some((&length_char, encoded)) = line.as_bytes().split_first() else {
+            continue;
+        };
+
+        let length = usize::from(uu_value(length_char));
+
+        if length == 0 {
+            continue;
+        }
+
+        let mut decoded_line = Vec::with_capacity(length + 2);
+
+        for chunk in encoded.chunks(4) {
+            let mut group = [0u8; 4];
+
+            for (idx, c) in chunk.iter().enumerate() {
+                if !(b' '..=b'`').contains(c) {
+                    return Err(format!("Invalid uuencode character: '{}'", char::from(*c)).into());
+                }
+                group[idx] = uu_value(*c);
+            }
+
+            decoded_line.push((group[0] << 2) | (group[1] >> 4));
+            decoded_line.push((group[1] << 4) | (group[2] >> 2));
+            decoded_line.push((group[2] << 6) | group[3]);
+        }
+
+        if decoded_line.len() < length {
+            return Err("Truncated uuencode line".into());
+        }
+
+        result.data.extend_from_slice(&decoded_line[..length]);
+    }
+
+    Ok(result)
+}
+
+#[inline]
+fn uu_value(c: u8) -> u8 {
+    c.wrapping_sub(b' ') & 0x3F
+}
+
+#[inline]
+fn uu_char(value: u8) -> char {
+    // A zero is written as a backtick rather than a space, so lines won't end with whitespace.
+    if value == 0 {
+        '`'
+    } else {
+        char::from(value + b' ')
+    }
+}
+
+pub fn encode_uuencode(src: &[u8], name: &str, mode: &str) -> Result<String, String> {
+    if !is_uu_mode(mode) {
+        return Err(format!(
+            "The mode must be 3 or 4 octal digits, e.g. 644, got: {mode:?}"
+        ));
+    }
+
+    // A line break within the name would end the `begin` line early.
+    if name.is_empty() || name.contains(['\r', '\n']) {
+        return Err(format!(
+            "The name must be a non-empty single line, got: {name:?}"
+        ));
+    }
+
+    let mut result = format!("begin {mode} {name}\n");
+
+    for line in src.chunks(UU_LINE_LENGTH) {
+        // Chunks are never longer than `UU_LINE_LENGTH`, which fits within a `u8`.
+        #[allow(clippy::cast_possible_truncation)]
+        result.push(uu_char(line.len() as u8));
+
+        for chunk in line.chunks(3) {
+            let mut group = [0u8; 3];
+            group[..chunk.len()].copy_from_slice(chunk);
+
+            result.push(uu_char(group[0] >> 2));
+            result.push(uu_char(((group[0] << 4) | (group[1] >> 4)) & 0x3F));
+            result.push(uu_char(((group[1] << 2) | (group[2] >> 6)) & 0x3F));
+            result.push(uu_char(group[2] & 0x3F));
+        }
+
+        result.push('\n');
+    }
+
+    result.push_str("`\nend\n");
+    Ok(result)
+}
+
+/// Returns the value of a `key=value` pair within a yEnc control line.
+/// The `name` is always last, and may contain spaces.
+fn yenc_field<'a>(line: &'a str, key: &str) -> Option<&'a str> {
+    if key == "name" {
+        return line.split_once(" name=").map(|(_, name)| name.trim());
+    }
+
+    line.split_whitespace()
+        .find_map(|field| field.strip_prefix(key)?.strip_prefix('='))
+}
+
+/// Decodes yEnc data, verifying the size and CRC32 of the `=yend` line when present.
+pub fn decode_yenc(src: &[u8]) -> Result<DecodedFile, DecodingError> {
+    let mut result = DecodedFile::default();
+    let mut started = false;
+    let mut expected_size: Option<usize> = None;
+    let mut expected_crc: Option<u32> = None;
+
+    for line in src.split(|b| *b == b'\n') {
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+
+        if line.starts_with(b"=ybegin ") {
+            let line = String::from_utf8_lossy(line);
+            result.name = yenc_field(&line, "name").map(ToOwned::to_owned);
+            started = true;
+            continue;
+        }
+
+        if !started || line.starts_with(b"=ypart ") {
+            continue;
+        }
+
+        if line.starts_with(b"=yend") {
+            let line = String::from_utf8_lossy(line);
+            expected_size = yenc_field(&line, "size").and_then(|size| size.parse().ok());
+
+            // A multipart message carries the CRC of the part within `pcrc32`.
+            expected_crc = yenc_field(&line, "pcrc32")
+                .or_else(|| yenc_field(&line, "crc32"))
+                .and_then(|crc| u32::from_str_radix(crc, 16).ok());
+            break;
+        }
+
+        let mut bytes = line.iter();
+
+        while let Some(&b) = bytes.next() {
+            let value = if b == b'=' {
+                match bytes.next() {
+                    Some(&escaped) => escaped.wrapping_sub(64),
+                    None => break,
+                }
+            } else {
+                b
+            };
+
+            result.data.push(value.wrapping_sub(42));
+        }
+    }
+
+    if !started {
+        return Err("Missing `=ybegin` line".into());
+    }
+
+    if let Some(size) = expected_size {
+        // Within a multipart message, this is the size of the current part.
+        if size != result.data.len() {
+            return Err(format!(
+                "yEnc size mismatch: expected {size} bytes, decoded {} bytes",
+                result.data.len()
+            )
+            .into());
+        }
+    }
+
+    if let Some(crc) = expected_crc {
+        if crc != crc32fast::hash(&result.data) {
+            return Err("yEnc CRC32 mismatch".into());
+        }
+    }
+
+    Ok(result)
+}
+
+/// Decodes the data fork of a binhex 4.0 (`.hqx`) file.
+pub fn decode_binhex(src: &str) -> Result<DecodedFile, DecodingError> {
+    // The encoded data starts at the first `:` after the marker line, and ends at the next one.
+    let after_marker = match src.find(BINHEX_MARKER) {
+        Some(idx) => src[idx..].split_once('\n').map_or("", |(_, rest)| rest),
+        None => src,
+    };
+
+    let start = after_marker
+        .find(':')
+        .ok_or("Missing BinHex start marker `:`")?;
+
+    let encoded = &after_marker[start + 1..];
+    let encoded = &encoded[..encoded.find(':').ok_or("Missing BinHex end marker `:`")?];
+
+    // 6-bit decoding
+    let mut packed = Vec::with_capacity(encoded.len() * 3 / 4);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+
+    for c in encoded.bytes().filter(|c| !c.is_ascii_whitespace()) {
+        let value = BINHEX_ALPHABET
+            .iter()
+            .position(|a| *a == c)
+            .ok_or_else(|| format!("Invalid BinHex character: '{}'", char::from(c)))?;
+
+        // The alphabet has 64 characters, so the position always fits.
+        #[allow(clippy::cast_possible_truncation)]
+        let value = value as u32;
+
+        buffer = (buffer << 6) | value;
+        bits += 6;
+
+        if bits >= 8 {
+            bits -= 8;
+            // Only the lowest 8 bits are taken.
+            #[allow(clippy::cast_possible_truncation)]
+            packed.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+
+    let data = expand_binhex_runs(&packed)?;
+
+    parse_binhex_file(&data)
+}
+
+/// Expands the run-length encoding, where `c 0x90 n` means `c` repeated `n` times,
+/// and `0x90 0x00` means a literal `0x90`.
+fn expand_binhex_runs(src: &[u8]) -> Result<Vec<u8>, DecodingError> {
+    let mut result = Vec::with_capacity(src.len());
+    let mut bytes = src.iter();
+
+    while let Some(&b) = bytes.next() {
+        if b != BINHEX_RUN_LENGTH_MARKER {
+            result.push(b);
+            continue;
+        }
+
+        match bytes.next() {
+            Some(0) => result.push(BINHEX_RUN_LENGTH_MARKER),
+            Some(&count) => {
+                let &repeated = result.last().ok_or("Invalid BinHex run length")?;
+
+                for _ in 1..count {
+                    result.push(repeated);
+                }
+            }
+            None => break,
+        }
+    }
+
+    Ok(result)
+}
+
+fn parse_binhex_file(src: &[u8]) -> Result<DecodedFile, DecodingError> {
+    let truncated = || DecodingError::from("Truncated BinHex file");
+
+    let name_length = usize::from(*src.first().ok_or_else(truncated)?);
+
+    // name length, name, version, type, creator, flags, data length, resource length
+    let header_length = 1 + name_length + 1 + 4 + 4 + 2 + 4 + 4;
+    let header = src.get(..header_length).ok_or_else(truncated)?;
+
+    let read_u32 = |at: usize| -> usize {
+        let bytes: [u8; 4] = header[at..at + 4].try_into().unwrap_or_default();
+        u32::from_be_bytes(bytes) as usize
+    };
+
+    let data_length = read_u32(header_length - 8);
+
+    check_binhex_crc(src, 0, header_length)?;
+
+    let data_start = header_length + 2;
+    check_binhex_crc(src, data_start, data_length)?;
+
+    Ok(DecodedFile {
+        name: Some(String::from_utf8_lossy(&header[1..=name_length]).into_owned()),
+        data: src[data_start..data_start + data_length].to_vec(),
+    })
+}
+
+/// Checks the CRC which follows the `length` bytes starting at `start`.
+fn check_binhex_crc(src: &[u8], start: usize, length: usize) -> Result<(), DecodingError> {
+    let crc_bytes = src
+        .get(start + length..start + length + 2)
+        .ok_or("Truncated BinHex file")?;
+
+    let expected = u16::from_be_bytes([crc_bytes[0], crc_bytes[1]]);
+
+    if crc16_xmodem(&src[start..start + length]) == expected {
+        Ok(())
+    } else {
+        Err("BinHex CRC mismatch".into())
+    }
+}
+
+fn crc16_xmodem(src: &[u8]) -> u16 {
+    let mut crc = 0u16;
+
+    for b in src {
+        crc ^= u16::from(*b) << 8;
+
+        for _ in 0..8 {
+            crc = if crc & 0x8000 == 0 {
+                crc << 1
+            } else {
+                (crc << 1) ^ 0x1021
+            };
+        }
+    }
+
+    crc
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn uuencode_round_trip() {
+        let data: Vec<u8> = (0..=255).cycle().take(100).collect();
+
+        let encoded = encode_uuencode(&data, "bytes.bin", "0644").unwrap();
+        assert!(encoded.starts_with("begin 0644 bytes.bin\n"));
+
+        let decoded = decode_uuencode(&encoded).unwrap();
+        assert_eq!(decoded.name.as_deref(), Some("bytes.bin"));
+        assert_eq!(decoded.data, data);
+    }
+
+    #[test]
+    fn uuencode_rejects_a_line_break_in_the_name_and_a_bad_mode() {
+        assert!(encode_uuencode(b"x", "a\nbegin 777 b", "644").is_err());
+        assert!(encode_uuencode(b"x", "a\r", "644").is_err());
+        assert!(encode_uuencode(b"x", "a", "64").is_err());
+        assert!(encode_uuencode(b"x", "a", "0x644").is_err());
+        assert!(encode_uuencode(b"x", "a", "648").is_err());
+    }
+
+    #[test]
+    fn yenc_checks_the_crc() {
+        let crc = crc32fast::hash(b"hi");
+
+        // `h` and `i`, shifted by 42.
+        let mut valid = b"=ybegin line=128 size=2 name=hi.txt\r\n\x92\x93\r\n".to_vec();
+        valid.extend_from_slice(format!("=yend size=2 crc32={crc:08x}\r\n").as_bytes());
+
+        let decoded = decode_yenc(&valid).unwrap();
+        assert_eq!(decoded.name.as_deref(), Some("hi.txt"));
+        assert_eq!(decoded.data, b"hi");
+
+        let corrupt: &[u8] =
+            b"=ybegin line=128 size=2 name=hi.txt\r\n\x92\x94\r\n=yend size=2 crc32=00000000\r\n";
+        let Err(e) = decode_yenc(corrupt) else {
+            panic!("Expected a CRC mismatch");
+        };
+        assert_eq!(e.to_string(), "yEnc CRC32 mismatch");
+    }
+
+    #[test]
+    fn crc16_xmodem_check_value() {
+        assert_eq!(crc16_xmodem(b"123456789"), 0x31C3);
+    }
+
+    #[test]
+    fn binhex_run_lengths() {
+        assert_eq!(
+            expand_binhex_runs(&[b'A', 0x90, 4, 0x90, 0, b'B']).unwrap(),
+            [b'A', b'A', b'A', b'A', 0x90, b'B']
+        );
+        assert!(expand_binhex_runs(&[0x90, 3]).is_err());
+    }
+
+    #[test]
+    fn binhex_decodes_the_data_fork() {
+        // The header's zero bytes are run-length encoded, and the data starts with an escaped 0x90.
+        let src = "(This file must be converted with BinHex 4.0)\r\n\
+            :\"@%ZG(Kd!&4&@&4dG(Kd!*!&#!#3\"2V2N!\"\"N!9SDAb)!!!:\r\n";
+
+        let decoded = decode_binhex(src).unwrap();
+        assert_eq!(decoded.name.as_deref(), Some("a.txt"));
+        assert_eq!(decoded.data, b"\x90AAAAAhi");
+
+        let corrupt = src.replace("SDAb", "SDAc");
+        let Err(e) = decode_binhex(&corrupt) else {
+            panic!("Expected a CRC mismatch");
+        };
+        assert_eq!(e.to_string(), "BinHex CRC mismatch");
+    }
+}
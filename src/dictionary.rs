@@ -0,0 +1,250 @@
+use std::borrow::Cow;
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use aho_corasick::{AhoCorasick, MatchKind};
+use serde::{Deserialize, Serialize};
+
+use crate::normalization::case_fold;
+
+/// A keyword list compiled into Aho-Corasick automata, which find every term in a single pass over the text.
+/// Every search mode needs an automaton of its own, so they're only built once a search asks for them.
+pub struct Dictionary {
+    terms: Vec<String>,
+    exact: Automata,
+    /// Matches the case folded terms, for case-insensitive searches.
+    folded: Automata,
+}
+
+#[derive(Default)]
+struct Automata {
+    /// Reports every occurrence, for overlapping and whole word searches.
+    standard: OnceLock<AhoCorasick>,
+    leftmost_first: OnceLock<AhoCorasick>,
+    leftmost_longest: OnceLock<AhoCorasick>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct SearchOptions {
+    /// Unicode case-insensitive matching.
+    #[serde(default)]
+    pub case_insensitive: bool,
+
+    /// Only matches terms which aren't part of a longer word.
+    #[serde(default)]
+    pub whole_words: bool,
+
+    /// Where several terms start at the same position, prefers the longest one
+    /// rather than the one listed first.
+    #[serde(default)]
+    pub longest: bool,
+
+    /// Returns every occurrence of every term, even where they overlap.
+    #[serde(default)]
+    pub overlapping: bool,
+}
+
+#[derive(Serialize, Debug)]
+pub struct DictionaryMatch {
+    /// The term's position in the dictionary.
+    pub term_id: usize,
+    pub term: String,
+    /// The occurrence as it appears in the text.
+    pub text: String,
+    /// The UTF-8 byte offsets of the occurrence within the text.
+    pub start: usize,
+    pub end: usize,
+}
+
+#[derive(Serialize, Debug)]
+pub struct DictionaryInfo {
+    pub name: String,
+    pub terms: usize,
+}
+
+impl Dictionary {
+    /// Takes a term from every line, skipping blank lines and lines starting with `#`.
+    /// Builds the automaton of the default search, so a dictionary which is too large fails here.
+    pub fn parse(src: &str) -> Result<Self, String> {
+        let terms: Vec<String> = src
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(str::to_owned)
+            .collect();
+
+        let dictionary = Self {
+            terms,
+            exact: Automata::default(),
+            folded: Automata::default(),
+        };
+
+        dictionary.automaton(false, MatchKind::LeftmostFirst)?;
+
+        Ok(dictionary)
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, String> {
+        let contents = fs::read_to_string(path).map_err(|e| e.to_string())?;
+        Self::parse(&contents)
+    }
+
+    #[inline]
+    pub fn term_count(&self) -> usize {
+        self.terms.len()
+    }
+
+    fn automaton(&self, case_insensitive: bool, kind: MatchKind) -> Result<&AhoCorasick, String> {
+        let automata = if case_insensitive {
+            &self.folded
+        } else {
+            &self.exact
+        };
+
+        let cell = match kind {
+            MatchKind::LeftmostFirst => &automata.leftmost_first,
+            MatchKind::LeftmostLongest => &automata.leftmost_longest,
+            _ => &automata.standard,
+        };
+
+        if let Some(automaton) = cell.get() {
+            return Ok(automaton);
+        }
+
+        let mut builder = AhoCorasick::builder();
+        builder.match_kind(kind);
+
+        let automaton = if case_insensitive {
+            builder.build(self.terms.iter().map(|term| case_fold(term).into_owned()))
+        } else {
+            builder.build(&self.terms)
+        }
+        .map_err(|e| e.to_string())?;
+
+        // Two searches may build it at the same time, in which case the first one is kept.
+        Ok(cell.get_or_init(|| automaton))
+    }
+
+    /// Finds the leftmost occurrences, preferring the term listed first or, with `longest`,
+    /// the longest one. Overlapping and whole word searches look at every occurrence instead,
+    /// so a term which fails `whole_words` doesn't hide another one at the same position.
+    pub fn search(
+        &self,
+        src: &str,
+        options: &SearchOptions,
+    ) -> Result<Vec<DictionaryMatch>, String> {
+        let (text, offsets) = if options.case_insensitive {
+            let (folded, offsets) = fold_with_offsets(src);
+            (Cow::Owned(folded), Some(offsets))
+        } else {
+            (Cow::Borrowed(src), None)
+        };
+
+        // Maps a match within the (possibly folded) text back onto `src`.
+        let to_occurrence = |m: aho_corasick::Match| match &offsets {
+            Some(offsets) => {
+                let start = offsets[m.start()];
+                let last = offsets[m.end() - 1];
+                let end = last + src[last..].chars().next().map_or(0, char::len_utf8);
+
+                (start, end, m.pattern().as_usize())
+            }
+            None => (m.start(), m.end(), m.pattern().as_usize()),
+        };
+
+        let occurrences: Vec<(usize, usize, usize)> = if options.overlapping || options.whole_words
+        {
+            let mut occurrences: Vec<(usize, usize, usize)> = self
+                .automaton(options.case_insensitive, MatchKind::Standard)?
+                .find_overlapping_iter(text.as_ref())
+                .map(to_occurrence)
+                .collect();
+
+            if options.whole_words {
+                occurrences.retain(|&(start, end, _)| is_whole_word(src, start, end));
+            }
+
+            if !options.overlapping {
+                occurrences = leftmost(occurrences, options.longest);
+            }
+
+            occurrences
+        } else {
+            let kind = if options.longest {
+                MatchKind::LeftmostLongest
+            } else {
+                MatchKind::LeftmostFirst
+            };
+
+            self.automaton(options.case_insensitive, kind)?
+                .find_iter(text.as_ref())
+                .map(to_occurrence)
+                .collect()
+        };
+
+        Ok(occurrences
+            .into_iter()
+            .map(|(start, end, term_id)| DictionaryMatch {
+                term_id,
+                term: self.terms[term_id].clone(),
+                text: src[start..end].to_owned(),
+                start,
+                end,
+            })
+            .collect())
+    }
+}
+
+/// Picks the leftmost occurrences out of overlapping ones, as the leftmost automata would.
+fn leftmost(
+    mut occurrences: Vec<(usize, usize, usize)>,
+    longest: bool,
+) -> Vec<(usize, usize, usize)> {
+    if longest {
+        occurrences.sort_by_key(|&(start, end, term_id)| (start, usize::MAX - end, term_id));
+    } else {
+        occurrences.sort_by_key(|&(start, _, term_id)| (start, term_id));
+    }
+
+    let mut last_end = 0;
+
+    occurrences.retain(|&(start, end, _)| {
+        if start < last_end {
+            return false;
+        }
+
+        last_end = end;
+        true
+    });
+
+    occurrences
+}
+
+/// Case folds the text, along with the offset of the original character behind every byte,
+/// since folding may change the length of a character, e.g. `ß` becomes `ss`.
+/// Folding is done a character at a time, which gives the same result as folding the whole text.
+fn fold_with_offsets(src: &str) -> (String, Vec<usize>) {
+    let mut folded = String::with_capacity(src.len());
+    let mut offsets = Vec::with_capacity(src.len());
+    let mut buffer = [0u8; 4];
+
+    for (offset, c) in src.char_indices() {
+        let folded_char = case_fold(c.encode_utf8(&mut buffer));
+
+        folded.push_str(&folded_char);
+        offsets.extend(std::iter::repeat_n(offset, folded_char.len()));
+    }
+
+    (folded, offsets)
+}
+
+fn is_whole_word(src: &str, start: usize, end: usize) -> bool {
+    let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+
+    let before = src[..start].chars().next_back();
+    let after = src[end..].chars().next();
+
+    !before.is_some_and(is_word_char) && !after.is_some_and(is_word_char)
+}
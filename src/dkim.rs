@@ -0,0 +1,629 @@
+use std::collections::HashMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use ed25519_dalek::{Signature as Ed25519Signature, VerifyingKey as Ed25519PublicKey};
+use rsa::pkcs1::DecodeRsaPublicKey;
+use rsa::pkcs8::DecodePublicKey;
+use rsa::{Pkcs1v15Sign, RsaPublicKey};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+const REQUIRED_TAGS: [&str; 7] = ["v", "a", "b", "bh", "d", "h", "s"];
+
+#[derive(Serialize, Debug)]
+pub struct DkimVerification {
+    pub domain: Option<String>,
+    pub selector: Option<String>,
+    pub algorithm: Option<String>,
+    pub canonicalization: Option<String>,
+    pub pass: bool,
+    /// Why the verification failed, if it did.
+    pub reason: Option<String>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Canonicalization {
+    Simple,
+    Relaxed,
+}
+
+impl Canonicalization {
+    fn parse(src: &str) -> Result<Self, String> {
+        match src {
+            "simple" => Ok(Canonicalization::Simple),
+            "relaxed" => Ok(Canonicalization::Relaxed),
+            _ => Err(format!("unsupported canonicalization '{src}'")),
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Algorithm {
+    RsaSha256,
+    Ed25519Sha256,
+}
+
+/// A header field, kept exactly as it was received (including folding and the trailing CRLF).
+struct RawHeader<'a> {
+    name: &'a str,
+    raw: &'a str,
+}
+
+impl RawHeader<'_> {
+    /// Everything after the first `:`, including the trailing CRLF.
+    fn value(&self) -> &str {
+        self.raw.split_once(':').map_or("", |(_, value)| value)
+    }
+}
+
+/// Verifies every `DKIM-Signature` header of a raw message (RFC 6376 and RFC 8463).
+/// Instead of DNS queries, the public keys are looked up in `keys`, which maps
+/// `selector._domainkey.domain` into its TXT record value.
+pub fn verify_message(message: &str, keys: &HashMap<String, String>) -> Vec<DkimVerification> {
+    // Messages passed around as text tend to lose their CR chars.
+    let mut message = message.replace("\r\n", "\n").replace('\n', "\r\n");
+
+    let separator = message.find("\r\n\r\n");
+
+    // Without a body, the last header may end the message without a CRLF of its own.
+    if separator.is_none() && !message.ends_with("\r\n") {
+        message.push_str("\r\n");
+    }
+
+    let (header_section, body) = match separator {
+        Some(idx) => (&message[..idx + 2], &message[idx + 4..]),
+        None => (message.as_str(), ""),
+    };
+
+    let headers = split_headers(header_section);
+
+    headers
+        .iter()
+        .filter(|header| header.name.eq_ignore_ascii_case("DKIM-Signature"))
+        .map(|signature| verify_signature(signature, &headers, body, keys))
+        .collect()
+}
+
+fn split_headers(src: &str) -> Vec<RawHeader<'_>> {
+    let mut result = Vec::new();
+    let mut start = 0;
+
+    for (idx, _) in src.match_indices("\r\n") {
+        let next = idx + 2;
+        let is_folded = src[next..].starts_with([' ', '\t']);
+
+        if !is_folded {
+            let raw = &src[start..next];
+            let name = raw.split_once(':').map_or(raw, |(name, _)| name).trim();
+            result.push(RawHeader { name, raw });
+            start = next;
+        }
+    }
+
+    result
+}
+
+/// Parses a `tag=value; tag=value` list, as used by both the signature and the key record.
+fn parse_tags(src: &str) -> Result<HashMap<String, String>, String> {
+    let mut tags = HashMap::new();
+
+    for tag in src.split(';').map(str::trim).filter(|tag| !tag.is_empty()) {
+        let Some((name, value)) = tag.split_once('=') else {
+            return Err(format!("malformed tag '{tag}'"));
+        };
+
+        let name = name.trim().to_owned();
+        let value: String = value.split_whitespace().collect::<Vec<_>>().join(" ");
+
+        if tags.insert(name.clone(), value).is_some() {
+            return Err(format!("duplicate tag '{name}'"));
+        }
+    }
+
+    Ok(tags)
+}
+
+fn verify_signature(
+    signature: &RawHeader,
+    headers: &[RawHeader],
+    body: &str,
+    keys: &HashMap<String, String>,
+) -> DkimVerification {
+    let tags = parse_tags(signature.value()).unwrap_or_default();
+
+    let mut result = DkimVerification {
+        domain: tags.get("d").cloned(),
+        selector: tags.get("s").cloned(),
+        algorithm: tags.get("a").cloned(),
+        canonicalization: tags.get("c").cloned(),
+        pass: false,
+        reason: None,
+    };
+
+    match check_signature(signature, headers, body, keys) {
+        Ok(()) => result.pass = true,
+        Err(reason) => result.reason = Some(reason),
+    }
+
+    result
+}
+
+fn check_signature(
+    signature: &RawHeader,
+    headers: &[RawHeader],
+    body: &str,
+    keys: &HashMap<String, String>,
+) -> Result<(), String> {
+    let tags = parse_tags(signature.value())?;
+
+    if let Some(missing) = REQUIRED_TAGS.iter().find(|tag| !tags.contains_key(**tag)) {
+        return Err(format!("missing required tag '{missing}'"));
+    }
+
+    if tags["v"] != "1" {
+        return Err(format!("unsupported version '{}'", tags["v"]));
+    }
+
+    let algorithm = match tags["a"].to_lowercase().as_str() {
+        "rsa-sha256" => Algorithm::RsaSha256,
+        "ed25519-sha256" => Algorithm::Ed25519Sha256,
+        other => return Err(format!("unsupported algorithm '{other}'")),
+    };
+
+    let (header_canon, body_canon) = match tags.get("c").map(|c| c.to_lowercase()) {
+        None => (Canonicalization::Simple, Canonicalization::Simple),
+        Some(c) => match c.split_once('/') {
+            Some((header, body)) => (
+                Canonicalization::parse(header)?,
+                Canonicalization::parse(body)?,
+            ),
+            None => (Canonicalization::parse(&c)?, Canonicalization::Simple),
+        },
+    };
+
+    let domain = tags["d"].to_lowercase();
+
+    let signed_headers: Vec<String> = tags["h"]
+        .split(':')
+        .map(|name| name.trim().to_lowercase())
+        .collect();
+
+    if !signed_headers.iter().any(|name| name == "from") {
+        return Err("the 'From' header is not signed".into());
+    }
+
+    if let Some(identity) = tags.get("i") {
+        let identity_domain = identity
+            .rsplit('@')
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+
+        if identity_domain != domain && !identity_domain.ends_with(&format!(".{domain}")) {
+            return Err(format!(
+                "identity '{identity}' is not within domain '{domain}'"
+            ));
+        }
+    }
+
+    if let Some(expiration) = tags.get("x") {
+        let expiration: u64 = expiration
+            .parse()
+            .map_err(|_| format!("malformed expiration '{expiration}'"))?;
+
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+
+        if expiration < now {
+            return Err("signature expired".into());
+        }
+    }
+
+    // Body hash
+    let canonicalized_body = canonicalize_body(body, body_canon);
+    let mut canonicalized_body = canonicalized_body.as_bytes();
+
+    if let Some(length) = tags.get("l") {
+        let length: usize = length
+            .parse()
+            .map_err(|_| format!("malformed body length '{length}'"))?;
+
+        if length > canonicalized_body.len() {
+            return Err("body length tag exceeds the body".into());
+        }
+
+        canonicalized_body = &canonicalized_body[..length];
+    }
+
+    let body_hash = base64::encode(Sha256::digest(canonicalized_body));
+
+    if body_hash != tags["bh"].replace(' ', "") {
+        return Err("body hash mismatch".into());
+    }
+
+    let signed_data = signed_headers_data(signature, headers, &signed_headers, header_canon);
+
+    let signature_bytes = base64::decode(tags["b"].replace(' ', ""))
+        .map_err(|e| format!("malformed signature: {e}"))?;
+
+    let key_name = format!("{}._domainkey.{domain}", tags["s"].to_lowercase());
+
+    let key_record = keys
+        .iter()
+        .find(|(name, _)| name.trim_end_matches('.').eq_ignore_ascii_case(&key_name))
+        .map(|(_, record)| record)
+        .ok_or_else(|| format!("no key was supplied for '{key_name}'"))?;
+
+    let key_bytes = parse_key_record(key_record, algorithm)?;
+
+    verify_signed_data(algorithm, &key_bytes, &signed_data, &signature_bytes)
+}
+
+/// Builds the data covered by the signature: the signed headers, followed by the signature header itself.
+fn signed_headers_data(
+    signature: &RawHeader,
+    headers: &[RawHeader],
+    signed_headers: &[String],
+    header_canon: Canonicalization,
+) -> String {
+    let mut signed_data = String::new();
+    let mut used = vec![false; headers.len()];
+
+    for name in signed_headers {
+        // Multiple instances of the same header are signed from the bottom up.
+        let found = headers
+            .iter()
+            .enumerate()
+            .rev()
+            .find(|(idx, header)| !used[*idx] && header.name.eq_ignore_ascii_case(name));
+
+        if let Some((idx, header)) = found {
+            used[idx] = true;
+            signed_data.push_str(&canonicalize_header(header, header_canon));
+        }
+    }
+
+    let stripped_signature = strip_signature_value(signature.raw);
+    let stripped_signature = RawHeader {
+        name: signature.name,
+        raw: &stripped_signature,
+    };
+
+    signed_data
+        .push_str(canonicalize_header(&stripped_signature, header_canon).trim_end_matches("\r\n"));
+
+    signed_data
+}
+
+fn verify_signed_data(
+    algorithm: Algorithm,
+    key_bytes: &[u8],
+    signed_data: &str,
+    signature_bytes: &[u8],
+) -> Result<(), String> {
+    let signed_data_hash = Sha256::digest(signed_data.as_bytes());
+
+    match algorithm {
+        Algorithm::RsaSha256 => {
+            let key = RsaPublicKey::from_public_key_der(key_bytes)
+                .or_else(|_| RsaPublicKey::from_pkcs1_der(key_bytes))
+                .map_err(|e| format!("malformed RSA key: {e}"))?;
+
+            key.verify(
+                Pkcs1v15Sign::new::<Sha256>(),
+                &signed_data_hash,
+                signature_bytes,
+            )
+            .map_err(|_| "signature verification failed".to_owned())
+        }
+        Algorithm::Ed25519Sha256 => {
+            let key_bytes: [u8; 32] = key_bytes
+                .try_into()
+                .map_err(|_| "malformed Ed25519 key: expected 32 bytes".to_owned())?;
+
+            let key = Ed25519PublicKey::from_bytes(&key_bytes)
+                .map_err(|e| format!("malformed Ed25519 key: {e}"))?;
+
+            let signature = Ed25519Signature::from_slice(signature_bytes)
+                .map_err(|e| format!("malformed signature: {e}"))?;
+
+            key.verify_strict(&signed_data_hash, &signature)
+                .map_err(|_| "signature verification failed".to_owned())
+        }
+    }
+}
+
+/// Returns the decoded public key (`p=`) of a DKIM key record, after making sure it fits the algorithm.
+fn parse_key_record(record: &str, algorithm: Algorithm) -> Result<Vec<u8>, String> {
+    let tags = parse_tags(record).map_err(|e| format!("malformed key record: {e}"))?;
+
+    if tags.get("v").is_some_and(|v| v != "DKIM1") {
+        return Err("malformed key record: unsupported version".into());
+    }
+
+    let key_type = tags.get("k").map_or("rsa", String::as_str);
+
+    let expected_key_type = match algorithm {
+        Algorithm::RsaSha256 => "rsa",
+        Algorithm::Ed25519Sha256 => "ed25519",
+    };
+
+    if !key_type.eq_ignore_ascii_case(expected_key_type) {
+        return Err(format!("key type '{key_type}' doesn't match the algorithm"));
+    }
+
+    if let Some(hashes) = tags.get("h") {
+        if !hashes
+            .split(':')
+            .any(|h| h.trim().eq_ignore_ascii_case("sha256"))
+        {
+            return Err("key doesn't allow sha256".into());
+        }
+    }
+
+    let public_key = tags
+        .get("p")
+        .map(|p| p.replace(' ', ""))
+        .unwrap_or_default();
+
+    if public_key.is_empty() {
+        return Err("key revoked".into());
+    }
+
+    base64::decode(public_key).map_err(|e| format!("malformed key: {e}"))
+}
+
+/// Removes the value of the `b=` tag, leaving the rest of the header exactly as it was.
+fn strip_signature_value(raw: &str) -> String {
+    let (name, value) = raw.split_once(':').unwrap_or((raw, ""));
+
+    let tags: Vec<String> = value
+        .split(';')
+        .map(|tag| match tag.split_once('=') {
+            Some((tag_name, _)) if tag_name.trim() == "b" => format!("{tag_name}="),
+            _ => tag.to_owned(),
+        })
+        .collect();
+
+    let mut result = format!("{name}:{}", tags.join(";"));
+
+    // Keep the trailing CRLF in case it was part of the removed value.
+    if raw.ends_with("\r\n") && !result.ends_with("\r\n") {
+        result.push_str("\r\n");
+    }
+
+    result
+}
+
+fn canonicalize_header(header: &RawHeader, canonicalization: Canonicalization) -> String {
+    match canonicalization {
+        Canonicalization::Simple => header.raw.to_owned(),
+        Canonicalization::Relaxed => {
+            let value = header.value().replace("\r\n", "");
+            let value = collapse_whitespace(value.trim_matches([' ', '\t']));
+
+            format!("{}:{value}\r\n", header.name.to_lowercase())
+        }
+    }
+}
+
+fn canonicalize_body(body: &str, canonicalization: Canonicalization) -> String {
+    match canonicalization {
+        Canonicalization::Simple => format!("{}\r\n", body.trim_end_matches("\r\n")),
+        Canonicalization::Relaxed => {
+            let mut lines: Vec<String> = body
+                .split("\r\n")
+                .map(|line| collapse_whitespace(line.trim_end_matches([' ', '\t'])))
+                .collect();
+
+            while lines.last().is_some_and(String::is_empty) {
+                lines.pop();
+            }
+
+            lines.iter().fold(String::new(), |mut result, line| {
+                result.push_str(line);
+                result.push_str("\r\n");
+                result
+            })
+        }
+    }
+}
+
+/// Reduces every sequence of spaces and tabs into a single space.
+fn collapse_whitespace(src: &str) -> String {
+    let mut result = String::with_capacity(src.len());
+    let mut prev_whitespace = false;
+
+    for c in src.chars() {
+        if c == ' ' || c == '\t' {
+            if !prev_whitespace {
+                result.push(' ');
+            }
+            prev_whitespace = true;
+        } else {
+            result.push(c);
+            prev_whitespace = false;
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    // RFC 8463, appendix A.
+    const RFC8463_MESSAGE: &str = "DKIM-Signature: v=1; a=ed25519-sha256; c=relaxed/relaxed;\r
+ d=football.example.com; i=@football.example.com;\r
+ q=dns/txt; s=brisbane; t=1528637909; h=from : to :\r
+ subject : date : message-id : from : subject : date;\r
+ bh=2jUSOH9NhtVGCQWNr9BrIAPreKQjO6Sn7XIkfJVOzv8=;\r
+ b=/gCrinpcQOoIfuHNQIbq4pgh9kyIK3AQUdt9OdqQehSwhEIug4D11Bus\r
+ Fa3bT3FY5OsU7ZbnKELq+eXdp1Q1Dw==\r
+DKIM-Signature: v=1; a=rsa-sha256; c=relaxed/relaxed;\r
+ d=football.example.com; i=@football.example.com;\r
+ q=dns/txt; s=test; t=1528637909; h=from : to : subject :\r
+ date : message-id : from : subject : date;\r
+ bh=2jUSOH9NhtVGCQWNr9BrIAPreKQjO6Sn7XIkfJVOzv8=;\r
+ b=F45dVWDfMbQDGHJFlXUNB2HKfbCeLRyhDXgFpEL8GwpsRe0IeIixNTe3\r
+ DhCVlUrSjV4BwcVcOF6+FF3Zo9Rpo1tFOeS9mPYQTnGdaSGsgeefOsk2Jz\r
+ dA+L10TeYt9BgDfQNZtKdN1WO//KgIqXP7OdEFE4LjFYNcUxZQ4FADY+8=\r
+From: Joe SixPack <joe@football.example.com>\r
+To: Suzie Q <suzie@shopping.example.net>\r
+Subject: Is dinner ready?\r
+Date: Fri, 11 Jul 2003 21:00:37 -0700 (PDT)\r
+Message-ID: <20030712040037.46341.5F8J@football.example.com>\r
+\r
+Hi.\r
+\r
+We lost the game.  Are you hungry yet?\r
+\r
+Joe.\r
+";
+
+    fn rfc8463_keys() -> HashMap<String, String> {
+        HashMap::from([
+            (
+                "brisbane._domainkey.football.example.com".to_owned(),
+                "v=DKIM1; k=ed25519; p=11qYAYKxCrfVS/7TyWQHOg7hcvPapiMlrwIaaPcHURo=".to_owned(),
+            ),
+            (
+                "test._domainkey.football.example.com".to_owned(),
+                "v=DKIM1; k=rsa; \
+                 p=MIGfMA0GCSqGSIb3DQEBAQUAA4GNADCBiQKBgQDkHlOQoBTzWRiGs5V6NpP3idY6Wk08a5qhdR6wy5bdOKb2jLQiY/J16JYi0Qvx/byYzCNb3W91y3FutACDfzwQ/BC/e/8uBsCR+yz1Lxj+PL6lHvqMKrM3rG4hstT5QjvHO9PzoxZyVYLzBfO2EeC3Ip3G+2kryOTIKT+l/K4w3QIDAQAB"
+                    .to_owned(),
+            ),
+        ])
+    }
+
+    #[test]
+    fn rfc8463_signatures() {
+        let results = verify_message(RFC8463_MESSAGE, &rfc8463_keys());
+
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|result| result.pass), "{results:?}");
+        assert_eq!(results[0].algorithm.as_deref(), Some("ed25519-sha256"));
+        assert_eq!(results[1].algorithm.as_deref(), Some("rsa-sha256"));
+    }
+
+    #[test]
+    fn rfc8463_signatures_without_cr() {
+        let message = RFC8463_MESSAGE.replace("\r\n", "\n");
+
+        let results = verify_message(&message, &rfc8463_keys());
+        assert!(results.iter().all(|result| result.pass), "{results:?}");
+    }
+
+    #[test]
+    fn rfc8463_tampered_body() {
+        let message = RFC8463_MESSAGE.replace("hungry", "thirsty");
+
+        for result in verify_message(&message, &rfc8463_keys()) {
+            assert!(!result.pass);
+            assert_eq!(result.reason.as_deref(), Some("body hash mismatch"));
+        }
+    }
+
+    #[test]
+    fn rfc8463_tampered_header() {
+        let message = RFC8463_MESSAGE.replace("Is dinner ready?", "Is lunch ready?");
+
+        for result in verify_message(&message, &rfc8463_keys()) {
+            assert!(!result.pass);
+            assert_eq!(
+                result.reason.as_deref(),
+                Some("signature verification failed")
+            );
+        }
+    }
+
+    // RFC 6376, section 3.4.5.
+    const RFC6376_HEADERS: &str = "A: X\r\nB : Y\t\r\n\tZ  \r\n";
+    const RFC6376_BODY: &str = " C \r\nD \t E\r\n\r\n\r\n";
+
+    #[test]
+    fn rfc6376_header_canonicalization() {
+        let headers = split_headers(RFC6376_HEADERS);
+
+        let canonicalized = |canonicalization| {
+            headers
+                .iter()
+                .map(|header| canonicalize_header(header, canonicalization))
+                .collect::<String>()
+        };
+
+        assert_eq!(canonicalized(Canonicalization::Relaxed), "a:X\r\nb:Y Z\r\n");
+        assert_eq!(canonicalized(Canonicalization::Simple), RFC6376_HEADERS);
+    }
+
+    #[test]
+    fn rfc6376_body_canonicalization() {
+        assert_eq!(
+            canonicalize_body(RFC6376_BODY, Canonicalization::Relaxed),
+            " C\r\nD E\r\n"
+        );
+        assert_eq!(
+            canonicalize_body(RFC6376_BODY, Canonicalization::Simple),
+            " C \r\nD \t E\r\n"
+        );
+    }
+
+    #[test]
+    fn empty_body_canonicalization() {
+        assert_eq!(canonicalize_body("", Canonicalization::Simple), "\r\n");
+        assert_eq!(canonicalize_body("", Canonicalization::Relaxed), "");
+    }
+
+    #[test]
+    fn signature_value_stripping() {
+        assert_eq!(
+            strip_signature_value("DKIM-Signature: v=1; b=abc\r\n def; bh=xyz\r\n"),
+            "DKIM-Signature: v=1; b=; bh=xyz\r\n"
+        );
+        assert_eq!(
+            strip_signature_value("DKIM-Signature: v=1; bh=xyz;\r\n b=abc\r\n"),
+            "DKIM-Signature: v=1; bh=xyz;\r\n b=\r\n"
+        );
+    }
+
+    /// A signature over `body` with a correct body hash, but no key to check the rest against.
+    fn signature_with_length(body: &str, length: usize) -> String {
+        let canonicalized = canonicalize_body(body, Canonicalization::Simple);
+        let body_hash = base64::encode(Sha256::digest(&canonicalized.as_bytes()[..length]));
+
+        format!(
+            "DKIM-Signature: v=1; a=rsa-sha256; d=example.com; s=test; h=from; l={length}; bh={body_hash}; b=AAAA\r\n\
+             From: joe@example.com\r\n\r\n{body}"
+        )
+    }
+
+    #[test]
+    fn body_length_limit() {
+        let message = signature_with_length("Signed part.\r\n", 14) + "Appended part.\r\n";
+
+        let results = verify_message(&message, &HashMap::new());
+        assert_eq!(
+            results[0].reason.as_deref(),
+            Some("no key was supplied for 'test._domainkey.example.com'")
+        );
+
+        let message = signature_with_length("Short.\r\n", 8).replace("l=8", "l=100");
+
+        let results = verify_message(&message, &HashMap::new());
+        assert_eq!(
+            results[0].reason.as_deref(),
+            Some("body length tag exceeds the body")
+        );
+    }
+
+    #[test]
+    fn last_header_without_crlf() {
+        let message = "From: joe@example.com\r\nDKIM-Signature: v=1; a=rsa-sha256; d=example.com; s=test; h=from; bh=x; b=y";
+
+        let results = verify_message(message, &HashMap::new());
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].domain.as_deref(), Some("example.com"));
+    }
+}
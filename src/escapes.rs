@@ -0,0 +1,614 @@
+use std::fmt::Write;
+use std::iter::Peekable;
+use std::str::Chars;
+
+use serde::Deserialize;
+
+use crate::utils::DecodingError;
+
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EscapeStyle {
+    #[default]
+    Json,
+    #[serde(alias = "js")]
+    Javascript,
+    #[serde(alias = "py")]
+    Python,
+    Rust,
+    Java,
+    #[serde(alias = "cs", alias = "c#")]
+    Csharp,
+    /// Bash ANSI-C quoting: `$'...'`
+    #[serde(alias = "bash")]
+    Shell,
+    /// A single quoted SQL string literal, with quotes doubled.
+    Sql,
+    /// A double quoted CSV field, with quotes doubled.
+    Csv,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct EscapeOptions {
+    #[serde(default)]
+    pub style: EscapeStyle,
+
+    /// Escapes every non-ASCII character as well. Has no effect on the SQL and CSV styles.
+    #[serde(default)]
+    pub ascii: bool,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct UnescapeOptions {
+    /// When missing, the original C-like dialect is used.
+    #[serde(default)]
+    pub style: Option<EscapeStyle>,
+}
+
+/// Escapes `src` as a string literal of the given style.
+/// The shell, SQL and CSV styles include their surrounding quotes, the rest return the contents only.
+pub fn escape(src: &str, style: EscapeStyle, ascii: bool) -> String {
+    match style {
+        EscapeStyle::Sql => quote(src, '\''),
+        EscapeStyle::Csv => quote(src, '"'),
+        EscapeStyle::Shell => format!("$'{}'", escape_backslashes(src, style, ascii)),
+        _ => escape_backslashes(src, style, ascii),
+    }
+}
+
+/// Reverses `escape`. Returns bytes, since shell `\x` and octal escapes produce raw bytes
+/// rather than characters. Characters are always written as UTF-8.
+pub fn unescape(src: &str, style: EscapeStyle) -> Result<Vec<u8>, DecodingError> {
+    match style {
+        EscapeStyle::Sql => unquote(src, '\''),
+        EscapeStyle::Csv => unquote(src, '"'),
+        EscapeStyle::Shell => {
+            let contents = src
+                .strip_prefix("$'")
+                .and_then(|s| s.strip_suffix('\''))
+                .unwrap_or(src);
+
+            unescape_backslashes(contents, style)
+        }
+        _ => unescape_backslashes(src, style),
+    }
+}
+
+fn quote(src: &str, quote: char) -> String {
+    let mut result = String::with_capacity(src.len() + 2);
+
+    result.push(quote);
+
+    for c in src.chars() {
+        if c == quote {
+            result.push(quote);
+        }
+        result.push(c);
+    }
+
+    result.push(quote);
+    result
+}
+
+fn unquote(src: &str, quote: char) -> Result<Vec<u8>, DecodingError> {
+    let contents = src
+        .strip_prefix(quote)
+        .and_then(|s| s.strip_suffix(quote))
+        .filter(|_| src.len() >= 2)
+        .unwrap_or(src);
+
+    let mut result = String::with_capacity(contents.len());
+    let mut chars = contents.chars();
+
+    while let Some(c) = chars.next() {
+        if c == quote && chars.next() != Some(quote) {
+            return Err(format!("Unescaped {quote} within a quoted string").into());
+        }
+        result.push(c);
+    }
+
+    Ok(result.into_bytes())
+}
+
+/// The character following the backslash, for escapes that have a short form within the style.
+fn short_escape(c: char, style: EscapeStyle) -> Option<char> {
+    use EscapeStyle::{Csharp, Java, Javascript, Json, Python, Rust, Shell};
+
+    let escaped = match (c, style) {
+        ('\\', _) => '\\',
+        ('"', Json | Javascript | Python | Rust | Java | Csharp) => '"',
+        ('\'', Javascript | Python | Rust | Java | Csharp | Shell) => '\'',
+        ('\n', _) => 'n',
+        ('\r', _) => 'r',
+        ('\t', _) => 't',
+        ('\u{0008}', Json | Javascript | Python | Java | Csharp | Shell) => 'b',
+        ('\u{000C}', Json | Javascript | Python | Java | Csharp | Shell) => 'f',
+        ('\u{000B}', Javascript | Python | Csharp | Shell) => 'v',
+        ('\u{0007}', Python | Csharp | Shell) => 'a',
+        ('\u{001B}', Shell) => 'e',
+        ('\0', Rust | Csharp) => '0',
+        _ => return None,
+    };
+
+    Some(escaped)
+}
+
+fn escape_backslashes(src: &str, style: EscapeStyle, ascii: bool) -> String {
+    let mut result = String::with_capacity(src.len());
+
+    for c in src.chars() {
+        if let Some(escaped) = short_escape(c, style) {
+            result.push('\\');
+            result.push(escaped);
+        } else if c.is_control() || c == '\u{2028}' || c == '\u{2029}' || (ascii && !c.is_ascii()) {
+            push_code_point_escape(&mut result, c, style);
+        } else {
+            result.push(c);
+        }
+    }
+
+    result
+}
+
+fn push_code_point_escape(result: &mut String, c: char, style: EscapeStyle) {
+    let code_point = u32::from(c);
+
+    // The highest code point written as `\xHH`. Shell and Rust byte escapes stop at ASCII.
+    let hex_escape_limit = match style {
+        EscapeStyle::Javascript | EscapeStyle::Python => 0xFF,
+        EscapeStyle::Rust | EscapeStyle::Shell => 0x7F,
+        _ => 0,
+    };
+
+    // Writing into a `String` never fails.
+    let _ = match style {
+        // Quoting styles have no escape sequences.
+        EscapeStyle::Sql | EscapeStyle::Csv => {
+            result.push(c);
+            Ok(())
+        }
+        EscapeStyle::Json | EscapeStyle::Java => {
+            let mut utf16 = [0u16; 2];
+
+            for unit in c.encode_utf16(&mut utf16) {
+                let _ = write!(result, "\\u{unit:04x}");
+            }
+
+            Ok(())
+        }
+        _ if code_point <= hex_escape_limit => write!(result, "\\x{code_point:02x}"),
+        EscapeStyle::Javascript | EscapeStyle::Rust => write!(result, "\\u{{{code_point:x}}}"),
+        _ if code_point <= 0xFFFF => write!(result, "\\u{code_point:04x}"),
+        _ => write!(result, "\\U{code_point:08x}"),
+    };
+}
+
+fn push_char(result: &mut Vec<u8>, c: char) {
+    let mut buffer = [0u8; 4];
+    result.extend_from_slice(c.encode_utf8(&mut buffer).as_bytes());
+}
+
+fn push_code_point(result: &mut Vec<u8>, code_point: u32) -> Result<(), DecodingError> {
+    let c = char::from_u32(code_point)
+        .ok_or_else(|| format!("Invalid code point: U+{code_point:04X}"))?;
+
+    push_char(result, c);
+    Ok(())
+}
+
+/// Reads between `min` and `max` hex digits.
+fn read_hex(chars: &mut Peekable<Chars>, min: usize, max: usize) -> Result<u32, DecodingError> {
+    let mut value = 0u32;
+    let mut count = 0;
+
+    while count < max {
+        let Some(digit) = chars.peek().and_then(|c| c.to_digit(16)) else {
+            break;
+        };
+
+        chars.next();
+        value = (value << 4) | digit;
+        count += 1;
+    }
+
+    if count < min {
+        return Err("Invalid hex escape sequence".into());
+    }
+
+    Ok(value)
+}
+
+/// Reads a `{1F600}` code point, as used by JavaScript and Rust.
+fn read_braced_hex(chars: &mut Peekable<Chars>) -> Result<u32, DecodingError> {
+    if chars.next() != Some('{') {
+        return Err("Expected `{` after `\\u`".into());
+    }
+
+    let value = read_hex(chars, 1, 6)?;
+
+    if chars.next() != Some('}') {
+        return Err("Expected `}` to close `\\u{`".into());
+    }
+
+    Ok(value)
+}
+
+/// Reads up to `max` octal digits, following the already consumed `first` digit.
+fn read_octal(first: char, chars: &mut Peekable<Chars>, max: usize) -> u32 {
+    let mut value = first.to_digit(8).unwrap_or_default();
+
+    for _ in 1..max {
+        let Some(digit) = chars.peek().and_then(|c| c.to_digit(8)) else {
+            break;
+        };
+
+        chars.next();
+        value = (value << 3) | digit;
+    }
+
+    value
+}
+
+/// Reads a UTF-16 code unit, combining a high surrogate with the `\uXXXX` low surrogate that must follow it.
+fn read_utf16(chars: &mut Peekable<Chars>, style: EscapeStyle) -> Result<u32, DecodingError> {
+    let read_unit = |chars: &mut Peekable<Chars>| -> Result<u32, DecodingError> {
+        // Java allows any number of `u` characters: `\uuuu0041`
+        if style == EscapeStyle::Java {
+            while chars.next_if_eq(&'u').is_some() {}
+        }
+        read_hex(chars, 4, 4)
+    };
+
+    let unit = read_unit(chars)?;
+
+    if !(0xD800..=0xDBFF).contains(&unit) {
+        return Ok(unit);
+    }
+
+    if chars.next() != Some('\\') || chars.next() != Some('u') {
+        return Err("Unpaired surrogate".into());
+    }
+
+    let low = read_unit(chars)?;
+
+    if !(0xDC00..=0xDFFF).contains(&low) {
+        return Err("Unpaired surrogate".into());
+    }
+
+    Ok(0x10000 + ((unit - 0xD800) << 10) + (low - 0xDC00))
+}
+
+fn unescape_backslashes(src: &str, style: EscapeStyle) -> Result<Vec<u8>, DecodingError> {
+    let mut chars = src.chars().peekable();
+    let mut result = Vec::with_capacity(src.len());
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            push_char(&mut result, c);
+            continue;
+        }
+
+        let escape = chars.next().ok_or("Trailing backslash")?;
+
+        match style {
+            EscapeStyle::Json => unescape_json(escape, &mut chars, &mut result)?,
+            EscapeStyle::Javascript => unescape_javascript(escape, &mut chars, &mut result)?,
+            EscapeStyle::Python => unescape_python(escape, &mut chars, &mut result)?,
+            EscapeStyle::Rust => unescape_rust(escape, &mut chars, &mut result)?,
+            EscapeStyle::Java => unescape_java(escape, &mut chars, &mut result)?,
+            EscapeStyle::Csharp => unescape_csharp(escape, &mut chars, &mut result)?,
+            EscapeStyle::Shell => unescape_shell(escape, &mut chars, &mut result)?,
+            EscapeStyle::Sql | EscapeStyle::Csv => push_char(&mut result, escape),
+        }
+    }
+
+    Ok(result)
+}
+
+fn invalid_escape(escape: char) -> DecodingError {
+    format!("Invalid escape sequence: \\{escape}").into()
+}
+
+fn unescape_json(
+    escape: char,
+    chars: &mut Peekable<Chars>,
+    result: &mut Vec<u8>,
+) -> Result<(), DecodingError> {
+    match escape {
+        '"' | '\\' | '/' => push_char(result, escape),
+        'b' => result.push(0x08),
+        'f' => result.push(0x0C),
+        'n' => result.push(b'\n'),
+        'r' => result.push(b'\r'),
+        't' => result.push(b'\t'),
+        'u' => push_code_point(result, read_utf16(chars, EscapeStyle::Json)?)?,
+        _ => return Err(invalid_escape(escape)),
+    }
+
+    Ok(())
+}
+
+fn unescape_javascript(
+    escape: char,
+    chars: &mut Peekable<Chars>,
+    result: &mut Vec<u8>,
+) -> Result<(), DecodingError> {
+    match escape {
+        'b' => result.push(0x08),
+        'f' => result.push(0x0C),
+        'n' => result.push(b'\n'),
+        'r' => result.push(b'\r'),
+        't' => result.push(b'\t'),
+        'v' => result.push(0x0B),
+        '0' if !chars.peek().is_some_and(char::is_ascii_digit) => result.push(0),
+        'x' => push_code_point(result, read_hex(chars, 2, 2)?)?,
+        'u' if chars.peek() == Some(&'{') => push_code_point(result, read_braced_hex(chars)?)?,
+        'u' => push_code_point(result, read_utf16(chars, EscapeStyle::Javascript)?)?,
+        // Line continuation
+        '\r' => {
+            chars.next_if_eq(&'\n');
+        }
+        '\n' | '\u{2028}' | '\u{2029}' => {}
+        // Legacy octal escapes aren't allowed in strict mode.
+        '0'..='9' => return Err(invalid_escape(escape)),
+        // Any other character stands for itself.
+        _ => push_char(result, escape),
+    }
+
+    Ok(())
+}
+
+fn unescape_python(
+    escape: char,
+    chars: &mut Peekable<Chars>,
+    result: &mut Vec<u8>,
+) -> Result<(), DecodingError> {
+    match escape {
+        '\\' | '\'' | '"' => push_char(result, escape),
+        'a' => result.push(0x07),
+        'b' => result.push(0x08),
+        'f' => result.push(0x0C),
+        'n' => result.push(b'\n'),
+        'r' => result.push(b'\r'),
+        't' => result.push(b'\t'),
+        'v' => result.push(0x0B),
+        '0'..='7' => push_code_point(result, read_octal(escape, chars, 3))?,
+        'x' => push_code_point(result, read_hex(chars, 2, 2)?)?,
+        'u' => push_code_point(result, read_hex(chars, 4, 4)?)?,
+        'U' => push_code_point(result, read_hex(chars, 8, 8)?)?,
+        'N' => {
+            if chars.next() != Some('{') {
+                return Err("Expected `{` after `\\N`".into());
+            }
+
+            let name: String = chars.by_ref().take_while(|c| *c != '}').collect();
+
+            let c = unicode_names2::character(&name)
+                .ok_or_else(|| format!("Unknown character name: {name}"))?;
+
+            push_char(result, c);
+        }
+        // Line continuation
+        '\n' => {}
+        // Unrecognized escapes are left in the string as they are.
+        _ => {
+            result.push(b'\\');
+            push_char(result, escape);
+        }
+    }
+
+    Ok(())
+}
+
+fn unescape_rust(
+    escape: char,
+    chars: &mut Peekable<Chars>,
+    result: &mut Vec<u8>,
+) -> Result<(), DecodingError> {
+    match escape {
+        '\\' | '\'' | '"' => push_char(result, escape),
+        'n' => result.push(b'\n'),
+        'r' => result.push(b'\r'),
+        't' => result.push(b'\t'),
+        '0' => result.push(0),
+        'x' => {
+            let value = read_hex(chars, 2, 2)?;
+
+            if value > 0x7F {
+                return Err("`\\x` escapes must be at most `\\x7f`".into());
+            }
+
+            push_code_point(result, value)?;
+        }
+        'u' => push_code_point(result, read_braced_hex(chars)?)?,
+        // Line continuation, skipping the leading whitespace of the next line.
+        '\n' => while chars.next_if(|c| c.is_whitespace()).is_some() {},
+        _ => return Err(invalid_escape(escape)),
+    }
+
+    Ok(())
+}
+
+fn unescape_java(
+    escape: char,
+    chars: &mut Peekable<Chars>,
+    result: &mut Vec<u8>,
+) -> Result<(), DecodingError> {
+    match escape {
+        '\\' | '\'' | '"' => push_char(result, escape),
+        'b' => result.push(0x08),
+        'f' => result.push(0x0C),
+        'n' => result.push(b'\n'),
+        'r' => result.push(b'\r'),
+        't' => result.push(b'\t'),
+        's' => result.push(b' '),
+        // Octal escapes go up to `\377`, so only a leading 0-3 allows a third digit.
+        '0'..='3' => push_code_point(result, read_octal(escape, chars, 3))?,
+        '4'..='7' => push_code_point(result, read_octal(escape, chars, 2))?,
+        'u' => push_code_point(result, read_utf16(chars, EscapeStyle::Java)?)?,
+        _ => return Err(invalid_escape(escape)),
+    }
+
+    Ok(())
+}
+
+fn unescape_csharp(
+    escape: char,
+    chars: &mut Peekable<Chars>,
+    result: &mut Vec<u8>,
+) -> Result<(), DecodingError> {
+    match escape {
+        '\\' | '\'' | '"' => push_char(result, escape),
+        '0' => result.push(0),
+        'a' => result.push(0x07),
+        'b' => result.push(0x08),
+        'e' => result.push(0x1B),
+        'f' => result.push(0x0C),
+        'n' => result.push(b'\n'),
+        'r' => result.push(b'\r'),
+        't' => result.push(b'\t'),
+        'v' => result.push(0x0B),
+        'x' => push_code_point(result, read_hex(chars, 1, 4)?)?,
+        'u' => push_code_point(result, read_utf16(chars, EscapeStyle::Csharp)?)?,
+        'U' => push_code_point(result, read_hex(chars, 8, 8)?)?,
+        _ => return Err(invalid_escape(escape)),
+    }
+
+    Ok(())
+}
+
+fn unescape_shell(
+    escape: char,
+    chars: &mut Peekable<Chars>,
+    result: &mut Vec<u8>,
+) -> Result<(), DecodingError> {
+    match escape {
+        '\\' | '\'' | '"' | '?' => push_char(result, escape),
+        'a' => result.push(0x07),
+        'b' => result.push(0x08),
+        'e' | 'E' => result.push(0x1B),
+        'f' => result.push(0x0C),
+        'n' => result.push(b'\n'),
+        'r' => result.push(b'\r'),
+        't' => result.push(b'\t'),
+        'v' => result.push(0x0B),
+        // Octal and hex escapes stand for bytes, not characters.
+        '0'..='7' => result.push(read_octal(escape, chars, 3).to_le_bytes()[0]),
+        'x' => result.push(read_hex(chars, 1, 2)?.to_le_bytes()[0]),
+        'u' => push_code_point(result, read_hex(chars, 1, 4)?)?,
+        'U' => push_code_point(result, read_hex(chars, 1, 8)?)?,
+        // Control characters: `\cA`
+        'c' => {
+            let c = chars.next().ok_or("Expected a character after `\\c`")?;
+            let value = u8::try_from(c).map_err(|_| invalid_escape('c'))?;
+
+            result.push(value & 0x1F);
+        }
+        // Unrecognized escapes are left in the string as they are.
+        _ => {
+            result.push(b'\\');
+            push_char(result, escape);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use proptest::prelude::*;
+
+    const STYLES: [EscapeStyle; 9] = [
+        EscapeStyle::Json,
+        EscapeStyle::Javascript,
+        EscapeStyle::Python,
+        EscapeStyle::Rust,
+        EscapeStyle::Java,
+        EscapeStyle::Csharp,
+        EscapeStyle::Shell,
+        EscapeStyle::Sql,
+        EscapeStyle::Csv,
+    ];
+
+    proptest! {
+        #[test]
+        fn escape_round_trip(src in any::<String>(), ascii in any::<bool>()) {
+            for style in STYLES {
+                let escaped = escape(&src, style, ascii);
+                prop_assert_eq!(unescape(&escaped, style).unwrap(), src.as_bytes(), "{:?}: {}", style, escaped);
+            }
+        }
+
+        // Biased towards backslashes, quotes, control characters and digits following an escape.
+        #[test]
+        fn escape_round_trip_special(src in r#"[\\\\'"\x00-\x1f\x7f0-9a-fuxUN{} \u{80}\u{2028}😀]*"#, ascii in any::<bool>()) {
+            for style in STYLES {
+                let escaped = escape(&src, style, ascii);
+                prop_assert_eq!(unescape(&escaped, style).unwrap(), src.as_bytes(), "{:?}: {}", style, escaped);
+            }
+        }
+
+        #[test]
+        fn escape_ascii_only(src in any::<String>()) {
+            for style in STYLES {
+                if matches!(style, EscapeStyle::Sql | EscapeStyle::Csv) {
+                    continue;
+                }
+
+                prop_assert!(escape(&src, style, true).is_ascii());
+            }
+        }
+    }
+
+    #[test]
+    fn escape_styles() {
+        let src = "it's \"😀\"\n";
+
+        assert_eq!(
+            escape(src, EscapeStyle::Json, true),
+            r#"it's \"\ud83d\ude00\"\n"#
+        );
+        assert_eq!(
+            escape(src, EscapeStyle::Javascript, true),
+            r#"it\'s \"\u{1f600}\"\n"#
+        );
+        assert_eq!(
+            escape(src, EscapeStyle::Python, true),
+            r#"it\'s \"\U0001f600\"\n"#
+        );
+        assert_eq!(
+            escape(src, EscapeStyle::Csharp, true),
+            r#"it\'s \"\U0001f600\"\n"#
+        );
+        assert_eq!(
+            escape(src, EscapeStyle::Shell, false),
+            "$'it\\'s \"😀\"\\n'"
+        );
+        assert_eq!(escape(src, EscapeStyle::Sql, false), "'it''s \"😀\"\n'");
+        assert_eq!(
+            escape(src, EscapeStyle::Csv, false),
+            "\"it's \"\"😀\"\"\n\""
+        );
+    }
+
+    #[test]
+    fn unescape_styles() {
+        let unescaped =
+            |src: &str, style| String::from_utf8(unescape(src, style).unwrap()).unwrap();
+
+        assert_eq!(
+            unescaped(r"\N{GRINNING FACE}\x41\101", EscapeStyle::Python),
+            "😀AA"
+        );
+        assert_eq!(unescaped(r"\uuD83D\uDE00\101", EscapeStyle::Java), "😀A");
+        assert_eq!(unescaped(r"\u{1F600}A", EscapeStyle::Javascript), "😀A");
+        assert_eq!(unescaped(r"\x41\cA", EscapeStyle::Shell), "A\u{1}");
+        assert_eq!(unescaped(r"\x41\u{1F600}", EscapeStyle::Rust), "A😀");
+        assert_eq!(unescaped(r"\x41\U0001F600", EscapeStyle::Csharp), "A😀");
+
+        assert!(unescape(r"\ud83d", EscapeStyle::Json).is_err());
+        assert!(unescape(r"\x80", EscapeStyle::Rust).is_err());
+        assert!(unescape("'it's'", EscapeStyle::Sql).is_err());
+    }
+}
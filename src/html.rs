@@ -0,0 +1,443 @@
+use std::borrow::Cow;
+use std::fmt::Write;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// Elements which contents are never rendered.
+const SKIPPED_ELEMENTS: [&str; 6] = ["script", "style", "head", "template", "noscript", "title"];
+
+/// Elements which start and end on a line of their own.
+const BLOCK_ELEMENTS: [&str; 24] = [
+    "address",
+    "article",
+    "aside",
+    "center",
+    "dd",
+    "div",
+    "dl",
+    "dt",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "header",
+    "hr",
+    "li",
+    "main",
+    "nav",
+    "ol",
+    "section",
+    "table",
+    "tbody",
+    "tr",
+    "ul",
+];
+
+/// Elements which are separated from their surroundings by an empty line.
+const PARAGRAPH_ELEMENTS: [&str; 9] =
+    ["blockquote", "h1", "h2", "h3", "h4", "h5", "h6", "p", "pre"];
+
+lazy_static! {
+    /// A named, decimal or hex character reference, terminated by a semicolon.
+    static ref ENTITY_PATTERN: Regex =
+        Regex::new(r"&(?:[A-Za-z][A-Za-z0-9]*|#[0-9]+|#[xX][0-9A-Fa-f]+);").unwrap();
+}
+
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EntityEncodeMode {
+    /// Only `&`, `<` and `>`, which is enough for text content.
+    #[default]
+    Minimal,
+    /// Quotes as well, which is safe within both single and double quoted attributes.
+    Attribute,
+    /// Same as `Attribute`, along with every non-ASCII character as a numeric reference.
+    Ascii,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct EntityEncodeOptions {
+    #[serde(default)]
+    pub mode: EntityEncodeMode,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct HtmlToTextOptions {
+    /// Responds with a JSON containing the text along with a list of the extracted link URLs.
+    #[serde(default)]
+    pub links: bool,
+}
+
+#[derive(Serialize, Debug, Default)]
+pub struct HtmlText {
+    pub text: String,
+    pub links: Vec<String>,
+}
+
+enum List {
+    Unordered,
+    Ordered(usize),
+}
+
+struct OpenLink {
+    href: String,
+    text_start: usize,
+}
+
+/// Collects text while collapsing whitespace, the way a browser would render it.
+#[derive(Default)]
+struct TextBuilder {
+    text: String,
+    pending_space: bool,
+    pending_newlines: usize,
+}
+
+impl TextBuilder {
+    fn flush(&mut self) {
+        if self.pending_newlines > 0 {
+            if !self.text.is_empty() {
+                let trimmed_len = self.text.trim_end_matches(' ').len();
+                self.text.truncate(trimmed_len);
+
+                let existing_newlines = self.text.len() - self.text.trim_end_matches('\n').len();
+
+                for _ in existing_newlines..self.pending_newlines {
+                    self.text.push('\n');
+                }
+            }
+        } else if self.pending_space && !self.text.is_empty() && !self.text.ends_with('\n') {
+            self.text.push(' ');
+        }
+
+        self.pending_newlines = 0;
+        self.pending_space = false;
+    }
+
+    fn push_text(&mut self, src: &str) {
+        for c in src.chars() {
+            if c.is_ascii_whitespace() {
+                self.pending_space = true;
+            } else {
+                self.flush();
+                self.text.push(c);
+            }
+        }
+    }
+
+    /// Pushes text as is, without collapsing its whitespace.
+    fn push_raw(&mut self, src: &str) {
+        if src.is_empty() {
+            return;
+        }
+
+        self.flush();
+        self.text.push_str(src);
+    }
+
+    fn line_break(&mut self, newlines: usize) {
+        self.pending_newlines = self.pending_newlines.max(newlines);
+    }
+
+    fn forced_line_break(&mut self) {
+        self.flush();
+        self.text.push('\n');
+    }
+
+    fn finish(self) -> String {
+        self.text
+            .lines()
+            .map(str::trim_end)
+            .collect::<Vec<_>>()
+            .join("\n")
+            .trim_matches('\n')
+            .to_owned()
+    }
+}
+
+/// Converts HTML into plain text.
+/// Keeps the paragraph and line structure, renders lists with bullets or numbers, and links as `text (url)`.
+pub fn html_to_text(src: &str) -> HtmlText {
+    let mut builder = TextBuilder::default();
+    let mut links = Vec::new();
+    let mut lists: Vec<List> = Vec::new();
+    let mut open_link: Option<OpenLink> = None;
+    let mut pre_depth = 0usize;
+    let mut rest = src;
+
+    while !rest.is_empty() {
+        let Some(tag_start) = rest.find('<') else {
+            push_text(&mut builder, rest, pre_depth > 0);
+            break;
+        };
+
+        push_text(&mut builder, &rest[..tag_start], pre_depth > 0);
+        rest = &rest[tag_start..];
+
+        // Comments, doctype and CDATA
+        if let Some(after) = rest.strip_prefix("<!--") {
+            rest = after.find("-->").map_or("", |end| &after[end + 3..]);
+            continue;
+        }
+
+        if rest.starts_with("<!") || rest.starts_with("<?") {
+            rest = rest.find('>').map_or("", |end| &rest[end + 1..]);
+            continue;
+        }
+
+        let Some(tag) = Tag::parse(rest) else {
+            // A lone `<` which isn't a part of a tag.
+            push_text(&mut builder, "<", pre_depth > 0);
+            rest = &rest[1..];
+            continue;
+        };
+
+        rest = &rest[tag.len..];
+
+        let name = tag.name.as_str();
+
+        if !tag.closing && SKIPPED_ELEMENTS.contains(&name) {
+            rest = skip_element_contents(rest, name);
+            continue;
+        }
+
+        if PARAGRAPH_ELEMENTS.contains(&name) {
+            builder.line_break(2);
+        } else if BLOCK_ELEMENTS.contains(&name) {
+            builder.line_break(1);
+        }
+
+        match (name, tag.closing) {
+            ("br", _) => builder.forced_line_break(),
+            ("pre", false) => pre_depth += 1,
+            ("pre", true) => pre_depth = pre_depth.saturating_sub(1),
+            ("td" | "th", false) => builder.pending_space = true,
+            ("ul", false) => lists.push(List::Unordered),
+            ("ol", false) => {
+                let start = tag.attribute("start").and_then(|s| s.parse().ok());
+                lists.push(List::Ordered(start.unwrap_or(1)));
+            }
+            ("ul" | "ol", true) => {
+                lists.pop();
+            }
+            ("li", false) => {
+                let indent = "  ".repeat(lists.len().saturating_sub(1));
+
+                let bullet = match lists.last_mut() {
+                    Some(List::Ordered(n)) => {
+                        *n += 1;
+                        format!("{}. ", *n - 1)
+                    }
+                    _ => "* ".to_owned(),
+                };
+
+                builder.push_raw(&format!("{indent}{bullet}"));
+            }
+            ("a", false) => {
+                builder.flush();
+
+                open_link = tag.attribute("href").map(|href| OpenLink {
+                    href: href.trim().to_owned(),
+                    text_start: builder.text.len(),
+                });
+            }
+            ("a", true) => {
+                if let Some(link) = open_link.take() {
+                    close_link(&mut builder, &mut links, &link);
+                }
+            }
+            ("img", false) => {
+                if let Some(alt) = tag.attribute("alt") {
+                    builder.push_text(&alt);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if let Some(link) = open_link.take() {
+        close_link(&mut builder, &mut links, &link);
+    }
+
+    HtmlText {
+        text: builder.finish(),
+        links,
+    }
+}
+
+fn push_text(builder: &mut TextBuilder, src: &str, preformatted: bool) {
+    let decoded = html_escape::decode_html_entities(src);
+
+    if preformatted {
+        builder.push_raw(&decoded);
+    } else {
+        builder.push_text(&decoded);
+    }
+}
+
+fn close_link(builder: &mut TextBuilder, links: &mut Vec<String>, link: &OpenLink) {
+    if link.href.is_empty() || link.href.starts_with('#') || link.href.starts_with("javascript:") {
+        return;
+    }
+
+    links.push(link.href.clone());
+
+    let text = builder
+        .text
+        .get(link.text_start..)
+        .unwrap_or_default()
+        .trim();
+
+    // There's no point in repeating a link which is already written as its own text.
+    let url_without_scheme = link.href.trim_start_matches("mailto:");
+
+    if text != link.href && text != url_without_scheme {
+        builder.push_raw(&format!(" ({})", link.href));
+    }
+}
+
+/// Skips everything up to, and including, the closing tag of the given element.
+fn skip_element_contents<'a>(src: &'a str, name: &str) -> &'a str {
+    let closing_tag = format!("</{name}");
+    let lowered_src = src.to_ascii_lowercase();
+
+    match lowered_src.find(&closing_tag) {
+        Some(idx) => {
+            let after = &src[idx..];
+            after.find('>').map_or("", |end| &after[end + 1..])
+        }
+        None => "",
+    }
+}
+
+struct Tag {
+    name: String,
+    closing: bool,
+    attributes: Vec<(String, String)>,
+    /// The length of the whole tag in bytes, including the `<` and `>`.
+    len: usize,
+}
+
+impl Tag {
+    /// Parses a tag at the beginning of `src`, which is expected to start with `<`.
+    fn parse(src: &str) -> Option<Self> {
+        let mut chars = src.char_indices().skip(1).peekable();
+
+        let closing = chars.next_if(|(_, c)| *c == '/').is_some();
+
+        if !chars.peek().is_some_and(|(_, c)| c.is_ascii_alphabetic()) {
+            return None;
+        }
+
+        let mut name = String::new();
+
+        while let Some((_, c)) = chars.next_if(|(_, c)| c.is_ascii_alphanumeric()) {
+            name.push(c.to_ascii_lowercase());
+        }
+
+        let mut attributes = Vec::new();
+
+        loop {
+            while chars
+                .next_if(|(_, c)| c.is_ascii_whitespace() || *c == '/')
+                .is_some()
+            {}
+
+            let (idx, c) = chars.next()?;
+
+            if c == '>' {
+                return Some(Self {
+                    name,
+                    closing,
+                    attributes,
+                    len: idx + 1,
+                });
+            }
+
+            let mut attribute_name = String::from(c.to_ascii_lowercase());
+
+            while let Some((_, c)) = chars
+                .next_if(|(_, c)| !c.is_ascii_whitespace() && *c != '=' && *c != '>' && *c != '/')
+            {
+                attribute_name.push(c.to_ascii_lowercase());
+            }
+
+            while chars.next_if(|(_, c)| c.is_ascii_whitespace()).is_some() {}
+
+            let mut value = String::new();
+
+            if chars.next_if(|(_, c)| *c == '=').is_some() {
+                while chars.next_if(|(_, c)| c.is_ascii_whitespace()).is_some() {}
+
+                match chars.next_if(|(_, c)| *c == '"' || *c == '\'') {
+                    Some((_, quote)) => {
+                        for (_, c) in chars.by_ref() {
+                            if c == quote {
+                                break;
+                            }
+                            value.push(c);
+                        }
+                    }
+                    None => {
+                        while let Some((_, c)) =
+                            chars.next_if(|(_, c)| !c.is_ascii_whitespace() && *c != '>')
+                        {
+                            value.push(c);
+                        }
+                    }
+                }
+            }
+
+            attributes.push((attribute_name, value));
+        }
+    }
+
+    fn attribute(&self, name: &str) -> Option<String> {
+        self.attributes
+            .iter()
+            .find(|(attribute_name, _)| attribute_name == name)
+            .map(|(_, value)| html_escape::decode_html_entities(value).into_owned())
+    }
+}
+
+/// Whether `src` holds any HTML/XML character reference which actually decodes into something.
+/// Text such as `AT&T;` isn't mistaken for one.
+pub fn contains_entities(src: &str) -> bool {
+    ENTITY_PATTERN
+        .find_iter(src)
+        .any(|m| matches!(html_escape::decode_html_entities(m.as_str()), Cow::Owned(_)))
+}
+
+/// Decodes named (using the full HTML5 table), decimal and hex character references.
+#[inline]
+pub fn decode_entities(src: &str) -> Cow<'_, str> {
+    html_escape::decode_html_entities(src)
+}
+
+pub fn encode_entities(src: &str, mode: EntityEncodeMode) -> Cow<'_, str> {
+    match mode {
+        EntityEncodeMode::Minimal => html_escape::encode_text(src),
+        EntityEncodeMode::Attribute => html_escape::encode_quoted_attribute(src),
+        EntityEncodeMode::Ascii => {
+            let encoded = html_escape::encode_quoted_attribute(src);
+
+            if encoded.is_ascii() {
+                return encoded;
+            }
+
+            let mut result = String::with_capacity(encoded.len());
+
+            for c in encoded.chars() {
+                if c.is_ascii() {
+                    result.push(c);
+                } else {
+                    // Writing into a `String` never fails.
+                    let _ = write!(result, "&#x{:X};", u32::from(c));
+                }
+            }
+
+            Cow::Owned(result)
+        }
+    }
+}
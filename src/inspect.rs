@@ -0,0 +1,109 @@
+use icu_properties::props::{
+    BidiClass, DefaultIgnorableCodePoint, EastAsianWidth, GeneralCategory, NoncharacterCodePoint,
+    Script,
+};
+use icu_properties::{CodePointMapData, CodePointSetData, PropertyNamesLong, PropertyNamesShort};
+use serde::Serialize;
+use unicode_segmentation::UnicodeSegmentation;
+
+#[derive(Serialize, Debug)]
+pub struct Inspection {
+    pub grapheme_count: usize,
+    pub code_point_count: usize,
+    pub byte_count: usize,
+    pub graphemes: Vec<GraphemeInfo>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct GraphemeInfo {
+    pub grapheme: String,
+    /// The UTF-8 byte offset of the grapheme within the text.
+    pub offset: usize,
+    pub code_points: Vec<CodePointInfo>,
+}
+
+// The flags are independent properties, reported as they are.
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Serialize, Debug)]
+pub struct CodePointInfo {
+    pub char: char,
+    /// `U+05D0`
+    pub code_point: String,
+    pub name: Option<String>,
+    /// Short alias, e.g. `Lu`
+    pub general_category: &'static str,
+    pub script: &'static str,
+    /// Short alias, e.g. `AL`
+    pub bidi_class: &'static str,
+    /// Short alias, e.g. `W`
+    pub east_asian_width: &'static str,
+    /// Hex bytes, e.g. `D7 90`
+    pub utf8: String,
+    /// Hex code units, e.g. `D83D DE00`
+    pub utf16: String,
+    /// Default ignorable and format characters, which render as nothing.
+    pub invisible: bool,
+    pub control: bool,
+    pub private_use: bool,
+    pub noncharacter: bool,
+}
+
+pub fn inspect(src: &str) -> Inspection {
+    let graphemes: Vec<GraphemeInfo> = src
+        .grapheme_indices(true)
+        .map(|(offset, grapheme)| GraphemeInfo {
+            grapheme: grapheme.to_owned(),
+            offset,
+            code_points: grapheme.chars().map(inspect_char).collect(),
+        })
+        .collect();
+
+    Inspection {
+        grapheme_count: graphemes.len(),
+        code_point_count: src.chars().count(),
+        byte_count: src.len(),
+        graphemes,
+    }
+}
+
+pub fn inspect_char(c: char) -> CodePointInfo {
+    let general_category = CodePointMapData::<GeneralCategory>::new().get(c);
+
+    let mut utf8 = [0u8; 4];
+    let mut utf16 = [0u16; 2];
+
+    CodePointInfo {
+        char: c,
+        code_point: format!("U+{:04X}", u32::from(c)),
+        name: unicode_names2::name(c).map(|name| name.to_string()),
+        general_category: PropertyNamesShort::<GeneralCategory>::new()
+            .get(general_category)
+            .unwrap_or_default(),
+        script: PropertyNamesLong::<Script>::new()
+            .get(CodePointMapData::<Script>::new().get(c))
+            .unwrap_or_default(),
+        bidi_class: PropertyNamesShort::<BidiClass>::new()
+            .get(CodePointMapData::<BidiClass>::new().get(c))
+            .unwrap_or_default(),
+        east_asian_width: PropertyNamesShort::<EastAsianWidth>::new()
+            .get(CodePointMapData::<EastAsianWidth>::new().get(c))
+            .unwrap_or_default(),
+        utf8: c
+            .encode_utf8(&mut utf8)
+            .bytes()
+            .map(|b| format!("{b:02X}"))
+            .collect::<Vec<_>>()
+            .join(" "),
+        utf16: c
+            .encode_utf16(&mut utf16)
+            .iter()
+            .map(|unit| format!("{unit:04X}"))
+            .collect::<Vec<_>>()
+            .join(" "),
+        invisible: CodePointSetData::new::<DefaultIgnorableCodePoint>().contains(c)
+            || general_category == GeneralCategory::Format,
+        control: general_category == GeneralCategory::Control,
+        private_use: general_category == GeneralCategory::PrivateUse,
+        noncharacter: CodePointSetData::new::<NoncharacterCodePoint>().contains(c),
+    }
+}
@@ -0,0 +1,163 @@
+use encoding::DecoderTrap;
+use icu_properties::props::Script;
+use icu_properties::{CodePointMapData, PropertyNamesLong};
+use serde::{Deserialize, Serialize};
+use whatlang::dev::{raw_detect, RawLangInfo};
+
+use crate::utils::decode_bytes;
+
+#[derive(Deserialize, Debug)]
+pub struct LanguageOptions {
+    /// How many candidate languages to return, best first.
+    #[serde(default = "default_limit")]
+    pub limit: usize,
+}
+
+#[inline]
+fn default_limit() -> usize {
+    3
+}
+
+#[derive(Serialize, Debug)]
+pub struct LanguageDetection {
+    pub languages: Vec<LanguageCandidate>,
+    /// How clearly the best candidate stands out from the second best, between 0 and 1.
+    pub confidence: f64,
+    /// The best candidate stands out clearly enough to be relied on.
+    pub reliable: bool,
+    /// The script most of the letters are written in.
+    pub script: Option<&'static str>,
+    pub scripts: Vec<ScriptShare>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct LanguageCandidate {
+    /// The ISO 639-3 code.
+    pub code: &'static str,
+    pub name: &'static str,
+    /// How well the text matches the language, between 0 and 1. All the scores come from
+    /// a single pass, so they can be compared with each other.
+    pub score: f64,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ScriptShare {
+    pub script: &'static str,
+    /// The share of the letters written in this script, between 0 and 1.
+    pub share: f64,
+}
+
+/// Detects the language with the alphabet and trigram profiles built into `whatlang`.
+/// Scripts which are written in a single language, like Greek or Georgian, have a single candidate.
+pub fn detect(src: &str, limit: usize) -> LanguageDetection {
+    let info = whatlang::detect(src);
+
+    // `whatlang` keeps the scores of the other candidates to its `dev` API.
+    let scores = match raw_detect(src).lang_info {
+        Some(RawLangInfo::MultiScript(combined)) => combined.scores,
+        Some(RawLangInfo::OneScript(lang) | RawLangInfo::Mandarin(lang)) => vec![(lang, 1.0)],
+        None => Vec::new(),
+    };
+
+    let languages = scores
+        .into_iter()
+        .take(limit)
+        .map(|(lang, score)| LanguageCandidate {
+            code: lang.code(),
+            name: lang.eng_name(),
+            score,
+        })
+        .collect();
+
+    let scripts = script_shares(src);
+
+    LanguageDetection {
+        languages,
+        confidence: info.as_ref().map_or(0.0, whatlang::Info::confidence),
+        reliable: info.as_ref().is_some_and(whatlang::Info::is_reliable),
+        script: scripts.first().map(|share| share.script),
+        scripts,
+    }
+}
+
+/// Counts the letters of every script, most used first.
+/// Characters which are common to many scripts, like digits and punctuation, aren't counted.
+#[allow(clippy::cast_precision_loss)]
+pub fn script_shares(src: &str) -> Vec<ScriptShare> {
+    let scripts = CodePointMapData::<Script>::new();
+    let names = PropertyNamesLong::<Script>::new();
+
+    let mut counts: Vec<(&'static str, usize)> = Vec::new();
+
+    for script in src.chars().map(|c| scripts.get(c)) {
+        if matches!(script, Script::Common | Script::Inherited | Script::Unknown) {
+            continue;
+        }
+
+        let Some(name) = names.get(script) else {
+            continue;
+        };
+
+        match counts.iter_mut().find(|(counted, _)| *counted == name) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((name, 1)),
+        }
+    }
+
+    let total: usize = counts.iter().map(|(_, count)| count).sum();
+    counts.sort_by(|(_, a), (_, b)| b.cmp(a));
+
+    counts
+        .into_iter()
+        .map(|(script, count)| ScriptShare {
+            script,
+            share: count as f64 / total as f64,
+        })
+        .collect()
+}
+
+/// Picks the charset which decodes `src` into the most plausible text, for single-byte charsets
+/// such as windows-1251 and KOI8-R which decode anything without an error.
+/// Ties go to the earlier candidate.
+pub fn pick_charset<'a>(src: &[u8], candidates: &[&'a str]) -> Option<&'a str> {
+    let mut best: Option<(&'a str, f64)> = None;
+
+    for &candidate in candidates {
+        let Ok(text) = decode_bytes(src, candidate, DecoderTrap::Strict) else {
+            continue;
+        };
+
+        let score = plausibility(&text);
+
+        if best.is_none_or(|(_, best_score)| score > best_score) {
+            best = Some((candidate, score));
+        }
+    }
+
+    best.map(|(candidate, _)| candidate)
+}
+
+/// The confidence of the detected language, scaled down by the share of words whose case is mixed up,
+/// like `пРИВЕТ` or `оПХБЕР`, which is what the wrong Cyrillic charset tends to produce.
+#[allow(clippy::cast_precision_loss)]
+fn plausibility(text: &str) -> f64 {
+    let confidence = whatlang::detect(text).map_or(0.0, |info| info.confidence());
+
+    let words: Vec<&str> = text
+        .split(|c: char| !c.is_alphabetic())
+        .filter(|word| word.chars().count() > 1)
+        .collect();
+
+    if words.is_empty() {
+        return confidence;
+    }
+
+    let mixed_case = words.iter().filter(|word| is_mixed_case(word)).count();
+
+    confidence * (1.0 - mixed_case as f64 / words.len() as f64)
+}
+
+/// Anything but `lower`, `UPPER` and `Capitalized`.
+fn is_mixed_case(word: &str) -> bool {
+    word.chars().skip(1).any(char::is_uppercase) && word.chars().any(char::is_lowercase)
+}
@@ -0,0 +1,290 @@
+use std::borrow::Cow;
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::escapes::{self, EscapeStyle};
+use crate::html;
+use crate::urls;
+use crate::utils;
+
+const DEFAULT_DEPTH: usize = 8;
+const MAX_DEPTH: usize = 64;
+
+/// Base64, base32 and hex payloads shorter than this are too likely to be ordinary words.
+const MIN_BINARY_TEXT_LENGTH: usize = 8;
+
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+lazy_static! {
+    static ref MIME_WORD_PATTERN: Regex = Regex::new(r"=\?([^?\s]+)\?[BbQq]\?[^?\s]*\?=").unwrap();
+    static ref BASE64_PATTERN: Regex = Regex::new(r"^[A-Za-z0-9+/\-_]+={0,2}$").unwrap();
+    static ref BASE32_PATTERN: Regex = Regex::new(r"^[A-Z2-7]+=*$").unwrap();
+    static ref HEX_PATTERN: Regex = Regex::new(r"^(?:[0-9A-Fa-f]{2})+$").unwrap();
+    static ref QUOTED_PRINTABLE_PATTERN: Regex = Regex::new(r"=(?:[0-9A-F]{2}|\r?\n)").unwrap();
+    static ref PERCENT_PATTERN: Regex =
+        Regex::new(r"%(?:[0-9A-Fa-f]{2}|[uU][0-9A-Fa-f]{4})").unwrap();
+    static ref ESCAPE_PATTERN: Regex =
+        Regex::new(r"\\(?:x[0-9A-Fa-f]{2}|u[0-9A-Fa-f]{4}|U[0-9A-Fa-f]{8}|[0-7]{3})").unwrap();
+}
+
+#[derive(Deserialize, Debug)]
+pub struct LayersOptions {
+    /// The maximum number of layers to peel.
+    #[serde(default = "default_depth")]
+    pub depth: usize,
+}
+
+#[inline]
+fn default_depth() -> usize {
+    DEFAULT_DEPTH
+}
+
+#[derive(Serialize, Debug)]
+pub struct DecodedLayers {
+    pub text: String,
+    /// The layers in the order they were peeled, outermost first.
+    pub layers: Vec<DecodingLayer>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct DecodingLayer {
+    pub encoding: &'static str,
+    /// The charset the decoded bytes were read with, for layers which produce bytes.
+    pub charset: Option<String>,
+}
+
+/// Repeatedly detects and decodes encoding layers, until nothing more can be decoded
+/// or `depth` layers were peeled.
+pub fn decode_layers(src: &[u8], charset: &str, depth: usize) -> DecodedLayers {
+    let mut text = utils::attempt_decode(src, charset)
+        .map_or_else(|_| utils::to_utf8_lossy(src).into_owned(), Cow::into_owned);
+
+    let mut layers = Vec::new();
+
+    while layers.len() < depth.min(MAX_DEPTH) {
+        let Some((decoded, layer)) = decode_layer(&text, charset) else {
+            break;
+        };
+
+        if decoded == text {
+            break;
+        }
+
+        text = decoded;
+        layers.push(layer);
+    }
+
+    DecodedLayers { text, layers }
+}
+
+/// Detects and decodes a single layer, trying the most distinctive encodings first.
+fn decode_layer(src: &str, charset: &str) -> Option<(String, DecodingLayer)> {
+    let compact: String = src.split_whitespace().collect();
+
+    if let Some(captures) = MIME_WORD_PATTERN.captures(src) {
+        let decoded = utils::decode_mime_words(src);
+
+        if decoded != src {
+            let layer = DecodingLayer {
+                encoding: "mime",
+                charset: Some(captures[1].to_lowercase()),
+            };
+
+            return Some((decoded.into_owned(), layer));
+        }
+    }
+
+    if compact.len() >= MIN_BINARY_TEXT_LENGTH {
+        if BASE32_PATTERN.is_match(&compact) {
+            if let Some(decoded) = decode_base32(&compact).and_then(|b| bytes_to_text(&b, charset))
+            {
+                return Some(with_layer(decoded, "base32"));
+            }
+        }
+
+        if HEX_PATTERN.is_match(&compact) {
+            if let Some(decoded) = decode_hex(&compact).and_then(|b| bytes_to_text(&b, charset)) {
+                return Some(with_layer(decoded, "hex"));
+            }
+        }
+
+        if BASE64_PATTERN.is_match(&compact) {
+            let config = if compact.contains(['-', '_']) {
+                base64::URL_SAFE
+            } else {
+                base64::STANDARD
+            };
+
+            if let Some(decoded) = base64::decode_config(&compact, config)
+                .ok()
+                .and_then(|b| bytes_to_text(&b, charset))
+            {
+                return Some(with_layer(decoded, "base64"));
+            }
+        }
+    }
+
+    if QUOTED_PRINTABLE_PATTERN.is_match(src) {
+        if let Some(decoded) = quoted_printable::decode(src, quoted_printable::ParseMode::Robust)
+            .ok()
+            .and_then(|b| bytes_to_text(&b, charset))
+        {
+            return Some(with_layer(decoded, "quoted-printable"));
+        }
+    }
+
+    if PERCENT_PATTERN.is_match(src) {
+        let bytes = urls::percent_decode_bytes(src);
+
+        if let Some(decoded) = bytes_to_text(&bytes, charset) {
+            return Some(with_layer(decoded, "percent"));
+        }
+    }
+
+    if html::contains_entities(src) {
+        let layer = DecodingLayer {
+            encoding: "entities",
+            charset: None,
+        };
+
+        return Some((html::decode_entities(src).into_owned(), layer));
+    }
+
+    if ESCAPE_PATTERN.is_match(src) {
+        // Shell style reads `\x` as bytes and leaves unrecognized escapes as they are.
+        if let Some(decoded) = escapes::unescape(src, EscapeStyle::Shell)
+            .ok()
+            .and_then(|b| bytes_to_text(&b, charset))
+        {
+            return Some(with_layer(decoded, "escapes"));
+        }
+    }
+
+    None
+}
+
+fn with_layer(
+    (text, charset): (String, String),
+    encoding: &'static str,
+) -> (String, DecodingLayer) {
+    let layer = DecodingLayer {
+        encoding,
+        charset: Some(charset),
+    };
+
+    (text, layer)
+}
+
+/// Reads the bytes as UTF-8 when valid, or with the given charset otherwise.
+/// Returns `None` when the result doesn't look like text, which means the layer was misdetected.
+fn bytes_to_text(src: &[u8], charset: &str) -> Option<(String, String)> {
+    let (text, charset) = match std::str::from_utf8(src) {
+        Ok(text) => (text.to_owned(), "utf-8".to_owned()),
+        Err(_) => (
+            utils::attempt_decode(src, charset).ok()?.into_owned(),
+            charset.to_lowercase(),
+        ),
+    };
+
+    let looks_like_text = !text.is_empty()
+        && !text.contains(char::REPLACEMENT_CHARACTER)
+        && text
+            .chars()
+            .all(|c| !c.is_control() || matches!(c, '\t' | '\r' | '\n'));
+
+    looks_like_text.then_some((text, charset))
+}
+
+fn decode_hex(src: &str) -> Option<Vec<u8>> {
+    (0..src.len())
+        .step_by(2)
+        .map(|idx| u8::from_str_radix(src.get(idx..idx + 2)?, 16).ok())
+        .collect()
+}
+
+fn decode_base32(src: &str) -> Option<Vec<u8>> {
+    let data = src.trim_end_matches('=');
+
+    // Valid unpadded lengths leave no dangling partial byte.
+    if !matches!(data.len() % 8, 0 | 2 | 4 | 5 | 7) {
+        return None;
+    }
+
+    let mut result = Vec::with_capacity(data.len() * 5 / 8);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+
+    for c in data.bytes() {
+        let value = BASE32_ALPHABET.iter().position(|a| *a == c)?;
+
+        // The alphabet has 32 characters, so the position always fits.
+        #[allow(clippy::cast_possible_truncation)]
+        let value = value as u32;
+
+        buffer = (buffer << 5) | value;
+        bits += 5;
+
+        if bits >= 8 {
+            bits -= 8;
+            result.push((buffer >> bits).to_le_bytes()[0]);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+
+    Some(result)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// `café <b>` as entities, within quoted-printable, within base64.
+    fn layered() -> String {
+        base64::encode("caf=26eacute; =26lt;b=26gt;")
+    }
+
+    fn encodings(decoded: &DecodedLayers) -> Vec<&'static str> {
+        decoded.layers.iter().map(|layer| layer.encoding).collect()
+    }
+
+    #[test]
+    fn peels_every_layer_outermost_first() {
+        let decoded = decode_layers(layered().as_bytes(), "utf-8", DEFAULT_DEPTH);
+
+        assert_eq!(decoded.text, "café <b>");
+        assert_eq!(
+            encodings(&decoded),
+            ["base64", "quoted-printable", "entities"]
+        );
+        assert_eq!(decoded.layers[0].charset.as_deref(), Some("utf-8"));
+    }
+
+    #[test]
+    fn stops_at_the_depth_limit() {
+        let decoded = decode_layers(layered().as_bytes(), "utf-8", 2);
+
+        assert_eq!(decoded.text, "caf&eacute; &lt;b&gt;");
+        assert_eq!(encodings(&decoded), ["base64", "quoted-printable"]);
+
+        let decoded = decode_layers(layered().as_bytes(), "utf-8", 0);
+        assert_eq!(decoded.text, layered());
+        assert!(decoded.layers.is_empty());
+    }
+
+    #[test]
+    fn plain_text_has_no_layers() {
+        let decoded = decode_layers(b"Hello, world!", "utf-8", DEFAULT_DEPTH);
+
+        assert_eq!(decoded.text, "Hello, world!");
+        assert!(decoded.layers.is_empty());
+    }
+
+    #[test]
+    fn bytes_which_arent_utf8_are_read_with_the_charset() {
+        let decoded = decode_layers(b"636166E9", "windows-1252", DEFAULT_DEPTH);
+        assert_eq!(decoded.text, "café");
+        assert_eq!(decoded.layers[0].encoding, "hex");
+        assert_eq!(decoded.layers[0].charset.as_deref(), Some("windows-1252"));
+    }
+}
@@ -0,0 +1,643 @@
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+
+use mailparse::{addrparse, MailAddr, MailHeaderMap, MailParseError, SingleInfo};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::utils;
+use crate::whitespace;
+
+#[derive(Debug)]
+pub enum MailError {
+    Parsing(MailParseError),
+    Idna(String),
+}
+
+impl std::fmt::Display for MailError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MailError::Parsing(e) => write!(f, "Unable to parse: {e}"),
+            MailError::Idna(domain) => write!(f, "Unable to IDNA-normalize domain: '{domain}'"),
+        }
+    }
+}
+
+impl std::error::Error for MailError {}
+
+impl From<MailParseError> for MailError {
+    fn from(e: MailParseError) -> Self {
+        MailError::Parsing(e)
+    }
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct AddressOptions {
+    /// Converts the domain into its ASCII (Punycode) IDNA form.
+    #[serde(default)]
+    pub idna: bool,
+
+    #[serde(default)]
+    pub lowercase: bool,
+}
+
+#[derive(Serialize, Debug)]
+pub struct ParsedAddress {
+    pub display_name: Option<String>,
+    pub local_part: String,
+    pub domain: String,
+    pub group: Option<String>,
+}
+
+/// Parses a To/Cc/From header value into a flat list of addresses.
+/// Addresses found within a group (e.g. `friends: a@b.com, c@d.com;`) carry the group's name.
+pub fn parse_addresses(
+    src: &str,
+    options: &AddressOptions,
+) -> Result<Vec<ParsedAddress>, MailError> {
+    let unfolded_src = whitespace::unfold(src);
+
+    let mut result = Vec::new();
+
+    for addr in addrparse(unfolded_src.trim())?.iter() {
+        match addr {
+            MailAddr::Single(info) => result.push(to_parsed_address(info, None, options)?),
+            MailAddr::Group(group_info) => {
+                let group_name = utils::decode_mime_words(group_info.group_name.trim());
+
+                for info in &group_info.addrs {
+                    result.push(to_parsed_address(
+                        info,
+                        Some(group_name.to_string()),
+                        options,
+                    )?);
+                }
+            }
+        }
+    }
+
+    Ok(result)
+}
+
+fn to_parsed_address(
+    info: &SingleInfo,
+    group: Option<String>,
+    options: &AddressOptions,
+) -> Result<ParsedAddress, MailError> {
+    // `addrparse` makes sure there is an `@` within every address.
+    let (local_part, domain) = info.addr.rsplit_once('@').unwrap_or((&info.addr, ""));
+
+    let mut domain = domain.to_owned();
+
+    if options.idna {
+        domain = idna::domain_to_ascii(&domain).map_err(|_| MailError::Idna(domain.clone()))?;
+    }
+
+    if options.lowercase {
+        domain = domain.to_lowercase();
+    }
+
+    let display_name = info
+        .display_name
+        .as_deref()
+        .map(|name| utils::decode_mime_words(name.trim()).into_owned())
+        .filter(|name| !name.is_empty());
+
+    Ok(ParsedAddress {
+        display_name,
+        local_part: local_part.to_owned(),
+        domain,
+        group,
+    })
+}
+
+lazy_static! {
+    static ref IPV4_PATTERN: Regex = Regex::new(r"\b(?:\d{1,3}\.){3}\d{1,3}\b").unwrap();
+    static ref IPV6_PATTERN: Regex =
+        Regex::new(r"(?i)\[(?:IPv6:)?([0-9a-f]*:[0-9a-f:.]+)\]").unwrap();
+}
+
+const RECEIVED_CLAUSES: [&str; 6] = ["from", "by", "via", "with", "id", "for"];
+
+#[derive(Serialize, Debug, Default)]
+pub struct ReceivedHop {
+    pub hop: usize,
+    pub from: Option<String>,
+    pub from_ip: Option<String>,
+    pub by: Option<String>,
+    pub by_ip: Option<String>,
+    pub protocol: Option<String>,
+    pub id: Option<String>,
+    #[serde(rename = "for")]
+    pub for_addr: Option<String>,
+    pub date: Option<String>,
+    pub timestamp: Option<i64>,
+    /// Seconds passed since the previous hop.
+    pub delay: Option<i64>,
+    pub out_of_order: bool,
+    pub parseable: bool,
+    pub raw: String,
+}
+
+/// Extracts every `Received:` header out of the raw message headers, and returns them as hops
+/// in the order the message went through them (which is the reverse order of their appearance).
+pub fn analyze_received_hops(src: &[u8]) -> Result<Vec<ReceivedHop>, MailError> {
+    let (headers, _) = mailparse::parse_headers(src)?;
+
+    let mut hops: Vec<ReceivedHop> = headers
+        .get_all_values("Received")
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(idx, value)| parse_received(idx + 1, value))
+        .collect();
+
+    let mut prev_timestamp: Option<i64> = None;
+
+    for hop in &mut hops {
+        if let (Some(prev), Some(current)) = (prev_timestamp, hop.timestamp) {
+            let delay = current - prev;
+            hop.delay = Some(delay);
+            hop.out_of_order = delay < 0;
+        }
+
+        if hop.timestamp.is_some() {
+            prev_timestamp = hop.timestamp;
+        }
+    }
+
+    Ok(hops)
+}
+
+fn parse_received(hop: usize, value: &str) -> ReceivedHop {
+    let value = value.trim();
+
+    // The date always follows the last `;`. e.g. `from a by b with ESMTP; Mon, 1 Jan 2022 ...`
+    let (clauses, date) = match value.rsplit_once(';') {
+        Some((clauses, date)) => (clauses, Some(date.trim())),
+        None => (value, None),
+    };
+
+    let mut result = ReceivedHop {
+        hop,
+        raw: value.to_owned(),
+        date: date.filter(|d| !d.is_empty()).map(ToOwned::to_owned),
+        timestamp: date.and_then(|d| mailparse::dateparse(d).ok()),
+        ..ReceivedHop::default()
+    };
+
+    for (keyword, clause) in split_received_clauses(clauses) {
+        let (head, comment) = match clause.split_once(char::is_whitespace) {
+            Some((head, comment)) => (head.to_owned(), comment),
+            None => (clause.clone(), ""),
+        };
+
+        match keyword.as_str() {
+            "from" => {
+                result.from_ip = find_ip(&clause);
+                result.from = Some(head).filter(|h| !h.starts_with('('));
+            }
+            "by" => {
+                result.by_ip = find_ip(comment);
+                result.by = Some(head);
+            }
+            "with" => result.protocol = Some(head),
+            "id" => result.id = Some(head),
+            "for" => result.for_addr = Some(head.trim_matches(['<', '>']).to_owned()),
+            _ => {}
+        }
+    }
+
+    result.parseable = result.timestamp.is_some() && (result.from.is_some() || result.by.is_some());
+
+    result
+}
+
+/// Splits the clauses of a `Received:` header by their keywords, ignoring keywords within comments.
+fn split_received_clauses(src: &str) -> Vec<(String, String)> {
+    let mut result: Vec<(String, String)> = Vec::new();
+    let mut depth = 0usize;
+
+    for word in src.split_whitespace() {
+        let lowered_word = word.to_lowercase();
+
+        if depth == 0 && RECEIVED_CLAUSES.contains(&lowered_word.as_str()) {
+            result.push((lowered_word, String::new()));
+        } else if let Some((_, clause)) = result.last_mut() {
+            if !clause.is_empty() {
+                clause.push(' ');
+            }
+            clause.push_str(word);
+        }
+
+        depth += word.matches('(').count();
+        depth = depth.saturating_sub(word.matches(')').count());
+    }
+
+    result
+}
+
+fn find_ip(src: &str) -> Option<String> {
+    IPV6_PATTERN
+        .captures_iter(src)
+        .filter_map(|caps| caps.get(1))
+        .map(|ip| ip.as_str())
+        .chain(IPV4_PATTERN.find_iter(src).map(|ip| ip.as_str()))
+        .find(|ip| ip.parse::<IpAddr>().is_ok())
+        .map(ToOwned::to_owned)
+}
+
+const AUTH_RESULTS_HEADERS: [&str; 3] = [
+    "Authentication-Results",
+    "ARC-Authentication-Results",
+    "Received-SPF",
+];
+
+/// The registered Email Authentication Methods, plus Microsoft's `compauth`.
+const AUTH_METHODS: [&str; 15] = [
+    "arc",
+    "auth",
+    "compauth",
+    "dkim",
+    "dkim-adsp",
+    "dkim-atps",
+    "dmarc",
+    "dnswl",
+    "domainkeys",
+    "iprev",
+    "rrvs",
+    "sender-id",
+    "smime",
+    "spf",
+    "vbr",
+];
+
+#[derive(Serialize, Debug)]
+pub struct AuthResultsHeader {
+    pub header: String,
+    /// The ARC instance (`i=`) of an `ARC-Authentication-Results` header.
+    pub instance: Option<u32>,
+    pub authserv_id: Option<String>,
+    pub results: Vec<AuthMethodResult>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct AuthMethodResult {
+    pub method: String,
+    pub result: String,
+    pub reason: Option<String>,
+    /// e.g. `header.d`, `header.i`, `smtp.mailfrom` or, for `Received-SPF`, `client-ip` and `helo`.
+    pub properties: BTreeMap<String, String>,
+}
+
+impl AuthMethodResult {
+    /// Takes the parts of a `method[/version]=result` pair.
+    fn new(method: &str, result: &str) -> Self {
+        Self {
+            method: method.split('/').next().unwrap_or_default().to_owned(),
+            result: result.to_lowercase(),
+            reason: None,
+            properties: BTreeMap::new(),
+        }
+    }
+}
+
+/// Parses every `Authentication-Results` (RFC 8601), `ARC-Authentication-Results` (RFC 8617)
+/// and `Received-SPF` (RFC 7208) header out of the raw message headers, in order of appearance.
+pub fn parse_auth_results(src: &[u8]) -> Result<Vec<AuthResultsHeader>, MailError> {
+    let (headers, _) = mailparse::parse_headers(src)?;
+
+    Ok(headers
+        .iter()
+        .filter_map(|header| {
+            let key = header.get_key();
+
+            let header_name = AUTH_RESULTS_HEADERS
+                .iter()
+                .find(|name| name.eq_ignore_ascii_case(key.trim()))?;
+
+            let value = header.get_value();
+
+            Some(if *header_name == "Received-SPF" {
+                parse_received_spf(&value)
+            } else {
+                parse_authentication_results(header_name, &value)
+            })
+        })
+        .collect())
+}
+
+fn parse_authentication_results(header_name: &str, value: &str) -> AuthResultsHeader {
+    let mut segments = split_unquoted(&strip_comments(value), |c| c == ';')
+        .into_iter()
+        .map(|segment| segment.trim().to_owned())
+        .filter(|segment| !segment.is_empty())
+        .peekable();
+
+    // ARC-Authentication-Results: i=1; mx.example.org; spf=pass ...
+    let instance = segments
+        .next_if(|segment| segment.to_lowercase().starts_with("i="))
+        .and_then(|segment| segment[2..].trim().parse().ok());
+
+    // The authserv-id may be followed by a version number, e.g. `mx.example.org 1`.
+    let authserv_id = segments
+        .next_if(|segment| !segment.contains('='))
+        .and_then(|segment| segment.split_whitespace().next().map(ToOwned::to_owned));
+
+    let mut results = Vec::new();
+
+    for segment in segments {
+        let mut result: Option<AuthMethodResult> = None;
+
+        for token in split_unquoted(&segment, char::is_whitespace) {
+            let Some((key, value)) = token.split_once('=') else {
+                continue;
+            };
+
+            let key = key.trim().to_lowercase();
+            let value = unquote(value.trim());
+
+            match result.as_mut() {
+                None => result = Some(AuthMethodResult::new(&key, &value)),
+                Some(current) if key == "reason" => current.reason = Some(value),
+                // A new `method=result` without a separating `;`, as some servers produce.
+                Some(_) if is_auth_method(&key) => {
+                    results.extend(result.take());
+                    result = Some(AuthMethodResult::new(&key, &value));
+                }
+                // Mostly `ptype.property`, but also vendor specific properties such as `action=none`.
+                Some(current) => {
+                    current.properties.insert(key, value);
+                }
+            }
+        }
+
+        results.extend(result);
+    }
+
+    AuthResultsHeader {
+        header: header_name.to_owned(),
+        instance,
+        authserv_id,
+        results,
+    }
+}
+
+fn is_auth_method(key: &str) -> bool {
+    let method = key.split('/').next().unwrap_or_default();
+    AUTH_METHODS.contains(&method) || method.starts_with("x-")
+}
+
+/// e.g. `Received-SPF: pass (mx.example.org: domain of a@b.com designates 1.2.3.4 as permitted sender)
+/// client-ip=1.2.3.4; envelope-from=a@b.com; helo=b.com;`
+fn parse_received_spf(value: &str) -> AuthResultsHeader {
+    let value = value.trim();
+
+    let (verdict, rest) = value.split_once(char::is_whitespace).unwrap_or((value, ""));
+
+    // The comment is the only way to figure the reason, so we keep it.
+    let rest = rest.trim_start();
+    let reason = rest.starts_with('(').then(|| {
+        rest[1..]
+            .split(')')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_owned()
+    });
+
+    let mut properties = BTreeMap::new();
+
+    for token in split_unquoted(&strip_comments(rest), |c| c == ';' || c.is_whitespace()) {
+        if let Some((key, value)) = token.split_once('=') {
+            properties.insert(key.trim().to_lowercase(), unquote(value.trim()));
+        }
+    }
+
+    let authserv_id = properties.get("receiver").cloned();
+
+    AuthResultsHeader {
+        header: "Received-SPF".to_owned(),
+        instance: None,
+        authserv_id,
+        results: vec![AuthMethodResult {
+            reason,
+            properties,
+            ..AuthMethodResult::new("spf", verdict)
+        }],
+    }
+}
+
+/// Removes (possibly nested) comments, which are placed within parentheses, outside of quoted strings.
+fn strip_comments(src: &str) -> String {
+    let mut result = String::with_capacity(src.len());
+    let mut depth = 0usize;
+    let mut quoted = false;
+    let mut escaped = false;
+
+    for c in src.chars() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' if depth == 0 => quoted = !quoted,
+            '(' if !quoted => {
+                depth += 1;
+                continue;
+            }
+            ')' if !quoted && depth > 0 => {
+                depth -= 1;
+                continue;
+            }
+            _ => {}
+        }
+
+        if depth == 0 {
+            result.push(c);
+        }
+    }
+
+    result
+}
+
+/// Splits `src` on every char matching `is_separator`, unless it's within a quoted string.
+fn split_unquoted(src: &str, is_separator: impl Fn(char) -> bool) -> Vec<String> {
+    let mut result = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+
+    for c in src.chars() {
+        if c == '"' {
+            quoted = !quoted;
+        }
+
+        if !quoted && is_separator(c) {
+            if !current.is_empty() {
+                result.push(std::mem::take(&mut current));
+            }
+        } else {
+            current.push(c);
+        }
+    }
+
+    if !current.is_empty() {
+        result.push(current);
+    }
+
+    result
+}
+
+fn unquote(src: &str) -> String {
+    match src.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+        Some(unquoted) => unquoted.replace("\\\"", "\"").replace("\\\\", "\\"),
+        None => src.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn addresses_within_a_group_carry_its_name() {
+        let options = AddressOptions::default();
+        let addresses = parse_addresses(
+            "Friends: a@example.com, \"Bob\" <bob@example.org>;, carol@example.net",
+            &options,
+        )
+        .unwrap();
+
+        assert_eq!(addresses.len(), 3);
+        assert_eq!(addresses[0].local_part, "a");
+        assert_eq!(addresses[0].group.as_deref(), Some("Friends"));
+        assert_eq!(addresses[1].display_name.as_deref(), Some("Bob"));
+        assert_eq!(addresses[1].domain, "example.org");
+        assert_eq!(addresses[1].group.as_deref(), Some("Friends"));
+        assert_eq!(addresses[2].group, None);
+    }
+
+    #[test]
+    fn encoded_word_display_names_are_decoded() {
+        let options = AddressOptions::default();
+        let addresses = parse_addresses(
+            "=?UTF-8?B?16nXnNeV150=?= <shalom@example.com>,\r\n =?ISO-8859-1?Q?Andr=E9?= <andre@example.com>",
+            &options,
+        )
+        .unwrap();
+
+        assert_eq!(addresses[0].display_name.as_deref(), Some("שלום"));
+        assert_eq!(addresses[1].display_name.as_deref(), Some("André"));
+        assert_eq!(addresses[1].local_part, "andre");
+    }
+
+    #[test]
+    fn domains_are_idna_normalized_and_lowercased() {
+        let options = AddressOptions {
+            idna: true,
+            lowercase: true,
+        };
+        let addresses = parse_addresses("user@BÜCHER.Example", &options).unwrap();
+
+        assert_eq!(addresses[0].domain, "xn--bcher-kva.example");
+    }
+
+    #[test]
+    fn received_hops_are_ordered_from_the_origin() {
+        let headers = b"Received: from mx.example.org (mx.example.org [203.0.113.5])\r\n\
+            \tby inbox.example.com (Postfix) with ESMTPS id 4AB12\r\n\
+            \tfor <user@example.com>; Mon, 3 Jan 2022 10:00:30 +0000\r\n\
+            Received: from [IPv6:2001:db8::1] (unknown)\r\n\
+            \tby mx.example.org with ESMTPSA id xyz; Mon, 3 Jan 2022 10:00:00 +0000\r\n\
+            Subject: Hello\r\n\r\n";
+
+        let hops = analyze_received_hops(headers).unwrap();
+
+        assert_eq!(hops.len(), 2);
+
+        assert_eq!(hops[0].hop, 1);
+        assert_eq!(hops[0].from_ip.as_deref(), Some("2001:db8::1"));
+        assert_eq!(hops[0].by.as_deref(), Some("mx.example.org"));
+        assert_eq!(hops[0].protocol.as_deref(), Some("ESMTPSA"));
+        assert_eq!(hops[0].delay, None);
+
+        assert_eq!(hops[1].hop, 2);
+        assert_eq!(hops[1].from.as_deref(), Some("mx.example.org"));
+        assert_eq!(hops[1].from_ip.as_deref(), Some("203.0.113.5"));
+        assert_eq!(hops[1].by.as_deref(), Some("inbox.example.com"));
+        assert_eq!(hops[1].id.as_deref(), Some("4AB12"));
+        assert_eq!(hops[1].for_addr.as_deref(), Some("user@example.com"));
+        assert_eq!(hops[1].delay, Some(30));
+        assert!(!hops[1].out_of_order);
+        assert!(hops.iter().all(|hop| hop.parseable));
+    }
+
+    #[test]
+    fn received_hops_with_a_clock_skew_are_out_of_order() {
+        let headers = b"Received: by b.example.com; Mon, 3 Jan 2022 09:59:00 +0000\r\n\
+            Received: by a.example.com; Mon, 3 Jan 2022 10:00:00 +0000\r\n\
+            Received: by origin.example.com\r\n\r\n";
+
+        let hops = analyze_received_hops(headers).unwrap();
+
+        assert!(!hops[0].parseable);
+        assert_eq!(hops[1].delay, None);
+        assert_eq!(hops[2].delay, Some(-60));
+        assert!(hops[2].out_of_order);
+    }
+
+    #[test]
+    fn auth_results_with_comments() {
+        let headers = b"Authentication-Results: mx.example.com 1 (the authserv; id);\r\n\
+            \tspf=pass (sender IP is 192.0.2.1) smtp.mailfrom=example.org;\r\n\
+            \tdkim=fail reason=\"signature (b=) didn't verify\" header.d=example.org header.s=sel;\r\n\
+            \tdmarc=PASS (p=none; dis=none) header.from=example.org\r\n\r\n";
+
+        let headers = parse_auth_results(headers).unwrap();
+        let results = &headers[0].results;
+
+        assert_eq!(headers[0].header, "Authentication-Results");
+        assert_eq!(headers[0].authserv_id.as_deref(), Some("mx.example.com"));
+        assert_eq!(results.len(), 3);
+
+        assert_eq!(results[0].method, "spf");
+        assert_eq!(results[0].result, "pass");
+        assert_eq!(results[0].properties["smtp.mailfrom"], "example.org");
+
+        assert_eq!(results[1].method, "dkim");
+        assert_eq!(results[1].result, "fail");
+        assert_eq!(
+            results[1].reason.as_deref(),
+            Some("signature (b=) didn't verify")
+        );
+        assert_eq!(results[1].properties["header.s"], "sel");
+
+        assert_eq!(results[2].method, "dmarc");
+        assert_eq!(results[2].result, "pass");
+        assert_eq!(results[2].properties["header.from"], "example.org");
+    }
+
+    #[test]
+    fn arc_auth_results_and_received_spf() {
+        let headers = b"ARC-Authentication-Results: i=2; mx.example.com; dkim=pass header.d=a.org spf=none\r\n\
+            Received-SPF: softfail (mx.example.com: transitioning domain of a.org does not designate 192.0.2.1)\r\n\
+            \tclient-ip=192.0.2.1; envelope-from=\"x@a.org\"; helo=a.org; receiver=mx.example.com;\r\n\r\n";
+
+        let headers = parse_auth_results(headers).unwrap();
+
+        assert_eq!(headers[0].instance, Some(2));
+        assert_eq!(headers[0].authserv_id.as_deref(), Some("mx.example.com"));
+        assert_eq!(headers[0].results.len(), 2);
+        assert_eq!(headers[0].results[1].method, "spf");
+        assert_eq!(headers[0].results[1].result, "none");
+
+        let spf = &headers[1].results[0];
+
+        assert_eq!(headers[1].authserv_id.as_deref(), Some("mx.example.com"));
+        assert_eq!(spf.result, "softfail");
+        assert_eq!(
+            spf.reason.as_deref(),
+            Some("mx.example.com: transitioning domain of a.org does not designate 192.0.2.1")
+        );
+        assert_eq!(spf.properties["client-ip"], "192.0.2.1");
+        assert_eq!(spf.properties["envelope-from"], "x@a.org");
+    }
+}
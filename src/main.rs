@@ -1,13 +1,37 @@
 #![warn(clippy::all, clippy::pedantic)]
-#![allow(clippy::unused_async)]
+#![allow(clippy::unused_async, clippy::non_std_lazy_statics)]
 
 #[macro_use]
 extern crate lazy_static;
 
+mod case;
 mod cfglib;
+mod codecs;
+mod dictionary;
+mod dkim;
+mod escapes;
+mod html;
+mod inspect;
+mod language;
+mod layers;
+mod mail;
+mod mojibake;
+mod normalization;
+mod sanitize;
+mod security;
 mod services;
+mod similarity;
+mod stats;
+mod textops;
+mod tnef;
+mod tokenize;
+mod transliterate;
+mod urls;
 mod utils;
+mod whitespace;
 
+use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::Duration;
 
 use parking_lot::RwLock;
@@ -15,6 +39,7 @@ use parking_lot::RwLock;
 use actix_web::{App, HttpServer};
 use cfglib::{CfgFileError, Config, RelativeFilePath};
 use clap::{Arg, ArgMatches};
+use dictionary::Dictionary;
 use simple_logger::SimpleLogger;
 use utils::PatternsCache;
 
@@ -209,6 +234,30 @@ lazy_static! {
         RwLock::new(cache)
     };
 
+    static ref DICTIONARIES: RwLock<HashMap<String, Arc<Dictionary>>> = {
+
+        let mut dictionaries = HashMap::with_capacity(CFG.dictionaries.files.len());
+
+        for (name, path) in &CFG.dictionaries.files {
+
+            let path = RelativeFilePath::new(path);
+
+            match Dictionary::load(&path) {
+                Ok(dictionary) => {
+                    log::info!("Dictionary `{name}`: Loaded {} terms from '{path}'", dictionary.term_count());
+                    dictionaries.insert(name.clone(), Arc::new(dictionary));
+                },
+                Err(e) => {
+                    log::error!("Dictionary `{name}`: Unable to load '{path}': {e}");
+                    log::error!("Full path: '{path:?}'");
+                    std::process::exit(1);
+                }
+            }
+        }
+
+        RwLock::new(dictionaries)
+    };
+
 }
 
 pub const DEFAULT_CHARSET: &str = "utf-8";
@@ -267,6 +316,15 @@ async fn main() -> std::io::Result<()> {
     // Logger
     log::debug!("log_level = {}", CFG.logger.log_level);
 
+    // Dictionaries
+    log::debug!(
+        "dictionaries.max_upload_size = {}",
+        CFG.dictionaries.max_upload_size
+    );
+
+    // Loads the dictionaries before serving, so a missing file stops the server right away.
+    lazy_static::initialize(&DICTIONARIES);
+
     HttpServer::new(|| {
         App::new()
             .service(services::welcome)
@@ -275,14 +333,61 @@ async fn main() -> std::io::Result<()> {
             // .service(services::form_test)
             // .service(services::json_test)
             .service(services::unescape_charset)
+            .service(services::escape)
+            .service(services::decode_url)
+            .service(services::decode_url_charset)
+            .service(services::encode_url)
+            .service(services::parse_url)
+            .service(services::parse_url_charset)
+            .service(services::decode_entities)
+            .service(services::encode_entities)
+            .service(services::html_to_text)
+            .service(services::html_to_text_charset)
             .service(services::decode_base64)
             .service(services::decode_base64_charset)
+            .service(services::decode_uuencode)
+            .service(services::decode_uuencode_charset)
+            .service(services::encode_uuencode)
+            .service(services::decode_yenc)
+            .service(services::decode_yenc_charset)
+            .service(services::decode_binhex)
+            .service(services::decode_binhex_charset)
+            .service(services::decode_tnef)
             .service(services::decode_mime_header)
             .service(services::decode_mime_header_rfc822)
             .service(services::decode_quoted_printable)
             .service(services::decode_quoted_printable_charset)
+            .service(services::parse_addresses)
+            .service(services::received_hops)
+            .service(services::auth_results)
+            .service(services::verify_dkim)
             .service(services::decode_auto)
             .service(services::decode_auto_charset)
+            .service(services::decode_layers)
+            .service(services::decode_layers_charset)
+            .service(services::fix_mojibake)
+            .service(services::normalize)
+            .service(services::convert_case)
+            .service(services::transliterate_text)
+            .service(services::inspect_text)
+            .service(services::security_check)
+            .service(services::detect_language)
+            .service(services::confusable)
+            .service(services::compare_similarity)
+            .service(services::fuzzy_search)
+            .service(services::list_dictionaries)
+            .service(services::search_dictionary)
+            .service(services::upload_dictionary)
+            .service(services::delete_dictionary)
+            .service(services::sanitize_text)
+            .service(services::reverse)
+            .service(services::length)
+            .service(services::text_stats)
+            .service(services::words)
+            .service(services::sentences)
+            .service(services::truncate)
+            .service(services::substring)
+            .service(services::pad)
             .service(services::regex_capture_group)
             .service(services::regex_to_json)
     })
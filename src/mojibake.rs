@@ -0,0 +1,292 @@
+use encoding::types::EncodingRef;
+use encoding::{all, EncoderTrap};
+use serde::Serialize;
+
+/// Repeated rounds undo mojibake which went through the wrong decoding more than once.
+const MAX_ROUNDS: usize = 4;
+
+/// Charsets UTF-8 text is commonly misread as, by likelihood. Names are as `utils::decode_bytes` knows them.
+const MISREAD_CHARSETS: [(&str, EncodingRef); 17] = [
+    ("windows-1252", all::WINDOWS_1252),
+    ("windows-1250", all::WINDOWS_1250),
+    ("windows-1251", all::WINDOWS_1251),
+    ("windows-1253", all::WINDOWS_1253),
+    ("windows-1254", all::WINDOWS_1254),
+    ("windows-1255", all::WINDOWS_1255),
+    ("windows-1256", all::WINDOWS_1256),
+    ("windows-1257", all::WINDOWS_1257),
+    ("windows-1258", all::WINDOWS_1258),
+    ("iso-8859-2", all::ISO_8859_2),
+    ("iso-8859-5", all::ISO_8859_5),
+    ("iso-8859-7", all::ISO_8859_7),
+    ("iso-8859-15", all::ISO_8859_15),
+    ("koi8-r", all::KOI8_R),
+    ("koi8-u", all::KOI8_U),
+    ("ibm866", all::IBM866),
+    ("macintosh", all::MAC_ROMAN),
+];
+
+#[derive(Serialize, Debug, Default)]
+pub struct MojibakeFix {
+    pub text: String,
+    pub fixed: bool,
+    /// The transformations which were undone, in the order they were undone.
+    pub steps: Vec<MojibakeStep>,
+    /// How likely it is that the input was mojibake and the fix is right, between 0 and 1.
+    /// Grows with the number of repaired multi-byte sequences, and drops with every byte that was lost.
+    pub confidence: f64,
+}
+
+#[derive(Serialize, Debug)]
+pub struct MojibakeStep {
+    /// The original encoding of the text.
+    pub encoding: &'static str,
+    /// The charset the text was wrongly decoded with.
+    pub misread_as: &'static str,
+    /// The number of repaired character sequences.
+    pub sequences: usize,
+}
+
+#[derive(Default)]
+struct RepairStats {
+    decoded_chars: usize,
+    lost_bytes: usize,
+}
+
+/// Detects text which was encoded as UTF-8 but decoded with a single-byte charset, and reverses it.
+/// Works on each run of non-ASCII characters separately, so text which is only partly broken
+/// keeps its correct parts as they are.
+pub fn fix_mojibake(src: &str) -> MojibakeFix {
+    let mut result = MojibakeFix {
+        text: src.to_owned(),
+        ..MojibakeFix::default()
+    };
+
+    let mut stats = RepairStats::default();
+
+    for _ in 0..MAX_ROUNDS {
+        let Some(text) = fix_round(&result.text, &mut result.steps, &mut stats) else {
+            break;
+        };
+
+        result.text = text;
+        result.fixed = true;
+    }
+
+    if result.fixed {
+        result.confidence = confidence(&stats);
+    }
+
+    result
+}
+
+/// Each valid multi-byte sequence is unlikely to appear by chance within real text.
+#[allow(
+    clippy::cast_precision_loss,
+    clippy::cast_possible_truncation,
+    clippy::cast_possible_wrap
+)]
+fn confidence(stats: &RepairStats) -> f64 {
+    let chance = 0.2f64.powi(stats.decoded_chars.min(64) as i32);
+    let lost_ratio = stats.lost_bytes as f64 / (stats.decoded_chars + stats.lost_bytes) as f64;
+
+    (1.0 - chance) * (1.0 - lost_ratio)
+}
+
+fn fix_round(src: &str, steps: &mut Vec<MojibakeStep>, stats: &mut RepairStats) -> Option<String> {
+    let mut result = String::with_capacity(src.len());
+    let mut round_steps: Vec<MojibakeStep> = Vec::new();
+    let mut rest = src;
+
+    while !rest.is_empty() {
+        let run_start = rest.find(|c: char| !c.is_ascii()).unwrap_or(rest.len());
+        result.push_str(&rest[..run_start]);
+        rest = &rest[run_start..];
+
+        let run_end = rest.find(|c: char| c.is_ascii()).unwrap_or(rest.len());
+        let run = &rest[..run_end];
+        rest = &rest[run_end..];
+
+        if run.is_empty() {
+            continue;
+        }
+
+        match fix_run(run) {
+            Some((fixed, misread_as, lost_bytes)) => {
+                stats.decoded_chars += fixed.chars().filter(|c| !c.is_ascii()).count();
+                stats.lost_bytes += lost_bytes;
+                result.push_str(&fixed);
+
+                match round_steps
+                    .iter_mut()
+                    .find(|step| step.misread_as == misread_as)
+                {
+                    Some(step) => step.sequences += 1,
+                    None => round_steps.push(MojibakeStep {
+                        encoding: "utf-8",
+                        misread_as,
+                        sequences: 1,
+                    }),
+                }
+            }
+            None => result.push_str(run),
+        }
+    }
+
+    if round_steps.is_empty() {
+        return None;
+    }
+
+    steps.extend(round_steps);
+    Some(result)
+}
+
+/// Re-encodes a run of non-ASCII characters with each candidate charset, looking for valid UTF-8.
+/// Returns the fixed text, the charset it was misread as and the number of bytes that were lost.
+fn fix_run(run: &str) -> Option<(String, &'static str, usize)> {
+    for (name, encoding) in MISREAD_CHARSETS {
+        let Some(bytes) = encode_sloppy(run, encoding) else {
+            continue;
+        };
+
+        if let Ok(fixed) = std::str::from_utf8(&bytes) {
+            // A C1 control character means a wrong guess, rather than real text.
+            if !fixed.chars().any(|c| ('\u{80}'..='\u{9F}').contains(&c)) {
+                return Some((fixed.to_owned(), name, 0));
+            }
+            continue;
+        }
+
+        // Charsets which leave some bytes undefined (e.g. 0x9D in windows-1252) lose them along the way.
+        // Accept the run when most of it decodes, marking each broken sequence.
+        let repair = decode_lossy(&bytes);
+
+        if repair.evidence > 0 && repair.evidence >= repair.lost {
+            return Some((repair.text, name, repair.lost + repair.truncated));
+        }
+    }
+
+    None
+}
+
+struct LossyRepair {
+    text: String,
+    /// Decoded characters, along with sequences which only lost their last bytes.
+    evidence: usize,
+    truncated: usize,
+    /// Lone bytes which aren't a part of any sequence.
+    lost: usize,
+}
+
+fn decode_lossy(src: &[u8]) -> LossyRepair {
+    let mut repair = LossyRepair {
+        text: String::with_capacity(src.len()),
+        evidence: 0,
+        truncated: 0,
+        lost: 0,
+    };
+
+    let mut rest = src;
+
+    loop {
+        let (valid, error) = match std::str::from_utf8(rest) {
+            Ok(valid) => (valid, None),
+            Err(e) => (
+                std::str::from_utf8(&rest[..e.valid_up_to()]).unwrap_or_default(),
+                Some(e),
+            ),
+        };
+
+        repair.text.push_str(valid);
+        repair.evidence += valid.chars().count();
+
+        let Some(error) = error else {
+            break;
+        };
+
+        rest = &rest[error.valid_up_to()..];
+        let broken_len = error.error_len().unwrap_or(rest.len());
+        rest = &rest[broken_len..];
+
+        repair.text.push(char::REPLACEMENT_CHARACTER);
+
+        // A lead byte followed by continuation bytes is a sequence which lost its last byte.
+        if broken_len >= 2 {
+            repair.truncated += 1;
+            repair.evidence += 1;
+        } else {
+            repair.lost += 1;
+        }
+    }
+
+    repair
+}
+
+/// Encodes with the given charset, passing C1 control characters through as their byte values.
+/// These stand for bytes the charset leaves undefined, which some decoders map this way.
+fn encode_sloppy(src: &str, encoding: EncodingRef) -> Option<Vec<u8>> {
+    if let Ok(bytes) = encoding.encode(src, EncoderTrap::Strict) {
+        return Some(bytes);
+    }
+
+    let mut result = Vec::with_capacity(src.len());
+    let mut buffer = [0u8; 4];
+
+    for c in src.chars() {
+        if let Some(byte) = u8::try_from(c).ok().filter(|b| (0x80..=0x9F).contains(b)) {
+            result.push(byte);
+        } else {
+            let bytes = encoding
+                .encode(c.encode_utf8(&mut buffer), EncoderTrap::Strict)
+                .ok()?;
+            result.extend_from_slice(&bytes);
+        }
+    }
+
+    Some(result)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn utf8_misread_as_windows_1252() {
+        let fixed = fix_mojibake("cafÃ© and naÃ¯ve");
+
+        assert!(fixed.fixed);
+        assert_eq!(fixed.text, "café and naïve");
+        assert_eq!(fixed.steps.len(), 1);
+        assert_eq!(fixed.steps[0].encoding, "utf-8");
+        assert_eq!(fixed.steps[0].misread_as, "windows-1252");
+        assert_eq!(fixed.steps[0].sequences, 2);
+        assert!(fixed.confidence > 0.5);
+    }
+
+    #[test]
+    fn a_byte_undefined_in_windows_1252_passed_through_as_a_c1_control() {
+        let fixed = fix_mojibake("Ñ\u{81}Ð¾Ð±Ð°ÐºÐ°");
+
+        assert!(fixed.fixed);
+        assert_eq!(fixed.text, "собака");
+    }
+
+    #[test]
+    fn double_mojibake_takes_two_rounds() {
+        let fixed = fix_mojibake("cafÃƒÂ©");
+
+        assert_eq!(fixed.text, "café");
+        assert_eq!(fixed.steps.len(), 2);
+    }
+
+    #[test]
+    fn correct_text_is_left_alone() {
+        for src in ["café", "собака", "שלום", "plain ASCII"] {
+            let fixed = fix_mojibake(src);
+
+            assert!(!fixed.fixed, "{src}");
+            assert_eq!(fixed.text, src);
+            assert!(fixed.steps.is_empty());
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use std::borrow::Cow;
+use std::str::FromStr;
+
+use icu_casemap::CaseMapper;
+use serde::Deserialize;
+use unicode_normalization::UnicodeNormalization;
+
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum NormalizationForm {
+    #[default]
+    Nfc,
+    Nfd,
+    Nfkc,
+    Nfkd,
+}
+
+impl FromStr for NormalizationForm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "nfc" => Ok(Self::Nfc),
+            "nfd" => Ok(Self::Nfd),
+            "nfkc" => Ok(Self::Nfkc),
+            "nfkd" => Ok(Self::Nfkd),
+            _ => Err(format!(
+                "Unknown normalization form: {s}. Expected one of: nfc, nfd, nfkc, nfkd"
+            )),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct NormalizeOptions {
+    /// Full Unicode case folding, e.g. `ß` becomes `ss`.
+    #[serde(default)]
+    pub case_fold: bool,
+
+    /// Removes the diacritics of Latin, Greek and Cyrillic, e.g. `é` becomes `e`.
+    /// The combining marks of other scripts, such as Indic vowel signs, are part of the letters and are kept.
+    #[serde(default)]
+    pub strip_accents: bool,
+}
+
+/// The `normalize` field of the regex, similarity and fuzzy search requests.
+#[derive(Deserialize, Debug, Default)]
+pub struct TextNormalization {
+    #[serde(default)]
+    pub form: NormalizationForm,
+
+    #[serde(flatten)]
+    pub options: NormalizeOptions,
+}
+
+/// The `normalize` field of `RegexData`.
+#[derive(Deserialize, Debug, Default)]
+pub struct RegexNormalization {
+    #[serde(flatten)]
+    pub text: TextNormalization,
+
+    /// Normalizes the pattern as well. Case folding is never applied to the pattern,
+    /// since it would change the meaning of escapes such as `\W`. Use `(?i)` instead.
+    #[serde(default)]
+    pub pattern: bool,
+}
+
+pub fn normalize(src: &str, form: NormalizationForm, options: &NormalizeOptions) -> String {
+    let mut result = if options.case_fold {
+        case_fold(src).into_owned()
+    } else {
+        src.to_owned()
+    };
+
+    if options.strip_accents {
+        result = result.nfd().filter(|c| !is_diacritic(*c)).collect();
+    }
+
+    match form {
+        NormalizationForm::Nfc => result.nfc().collect(),
+        NormalizationForm::Nfd => result.nfd().collect(),
+        NormalizationForm::Nfkc => result.nfkc().collect(),
+        NormalizationForm::Nfkd => result.nfkd().collect(),
+    }
+}
+
+/// Full Unicode case folding, for caseless matching. `Straße` and `STRASSE` both become `strasse`.
+/// Every endpoint which matches without case folds through here, so their results agree.
+#[inline]
+pub fn case_fold(src: &str) -> Cow<'_, str> {
+    CaseMapper::new().fold_string(src)
+}
+
+impl TextNormalization {
+    pub fn normalize_text(&self, src: &str) -> String {
+        normalize(src, self.form, &self.options)
+    }
+}
+
+impl RegexNormalization {
+    pub fn normalize_pattern(&self, src: &str) -> String {
+        if !self.pattern {
+            return src.to_owned();
+        }
+
+        let options = NormalizeOptions {
+            case_fold: false,
+            ..self.text.options
+        };
+
+        normalize(src, self.text.form, &options)
+    }
+}
+
+/// The combining diacritical mark blocks, plus the Cyrillic combining marks.
+fn is_diacritic(c: char) -> bool {
+    matches!(
+        c,
+        '\u{0300}'..='\u{036F}'
+            | '\u{0483}'..='\u{0489}'
+            | '\u{1AB0}'..='\u{1AFF}'
+            | '\u{1DC0}'..='\u{1DFF}'
+            | '\u{20D0}'..='\u{20FF}'
+            | '\u{FE20}'..='\u{FE2F}'
+    )
+}
@@ -0,0 +1,188 @@
+use icu_properties::props::{ExtendedPictographic, NoncharacterCodePoint};
+use icu_properties::CodePointSetData;
+use serde::{Deserialize, Serialize};
+
+use crate::security::{BIDI_CONTROLS, ZERO_WIDTH};
+
+const ZWNJ: char = '\u{200C}';
+const ZWJ: char = '\u{200D}';
+
+/// Every policy is enabled unless turned off.
+#[derive(Deserialize, Debug)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct SanitizeOptions {
+    /// C0 and C1 control characters, except tab, carriage return and line feed.
+    #[serde(default = "enabled")]
+    pub controls: bool,
+
+    /// Zero-width characters. Joiners which hold together an emoji sequence,
+    /// or the letters of a script that needs them, are kept.
+    #[serde(default = "enabled")]
+    pub zero_width: bool,
+
+    /// Bidi embeddings, overrides, isolates and marks.
+    #[serde(default = "enabled")]
+    pub bidi: bool,
+
+    /// Replaces non-breaking and other exotic spaces with an ASCII space,
+    /// and Unicode line separators with a line feed.
+    #[serde(default = "enabled")]
+    pub spaces: bool,
+
+    /// Collapses runs of spaces and tabs into a single space.
+    #[serde(default = "enabled")]
+    pub collapse_whitespace: bool,
+
+    #[serde(default = "enabled")]
+    pub trim: bool,
+
+    /// Replaces noncharacters with U+FFFD. Invalid UTF-8, including encoded surrogates, is always replaced.
+    #[serde(default = "enabled")]
+    pub noncharacters: bool,
+}
+
+#[inline]
+fn enabled() -> bool {
+    true
+}
+
+#[derive(Serialize, Debug)]
+pub struct Sanitized {
+    pub text: String,
+    pub changes: SanitizeChanges,
+}
+
+#[derive(Serialize, Debug, Default)]
+pub struct SanitizeChanges {
+    pub controls_removed: usize,
+    pub zero_width_removed: usize,
+    pub bidi_removed: usize,
+    pub spaces_replaced: usize,
+    pub whitespace_collapsed: usize,
+    pub trimmed: usize,
+    pub noncharacters_replaced: usize,
+    pub invalid_sequences_replaced: usize,
+}
+
+pub fn sanitize(src: &[u8], options: &SanitizeOptions) -> Sanitized {
+    let mut changes = SanitizeChanges::default();
+    let mut decoded = String::with_capacity(src.len());
+
+    // An encoded surrogate spans several invalid chunks, so each run of them is replaced once.
+    let mut is_in_invalid_run = false;
+
+    for chunk in src.utf8_chunks() {
+        if !chunk.valid().is_empty() {
+            decoded.push_str(chunk.valid());
+            is_in_invalid_run = false;
+        }
+
+        if !chunk.invalid().is_empty() && !is_in_invalid_run {
+            decoded.push(char::REPLACEMENT_CHARACTER);
+            changes.invalid_sequences_replaced += 1;
+            is_in_invalid_run = true;
+        }
+    }
+
+    let chars: Vec<char> = decoded.chars().collect();
+    let mut text = String::with_capacity(decoded.len());
+
+    let pictographic = CodePointSetData::new::<ExtendedPictographic>();
+    let noncharacters = CodePointSetData::new::<NoncharacterCodePoint>();
+
+    for (idx, &c) in chars.iter().enumerate() {
+        let prev = idx.checked_sub(1).map(|idx| chars[idx]);
+        let next = chars.get(idx + 1).copied();
+
+        if options.zero_width && ZERO_WIDTH.contains(&c) {
+            let is_needed = match c {
+                ZWJ => next.is_some_and(|next| pictographic.contains(next)),
+                ZWNJ => [prev, next]
+                    .iter()
+                    .all(|c| c.is_some_and(|c| c.is_alphabetic() && !c.is_ascii())),
+                _ => false,
+            };
+
+            if !is_needed {
+                changes.zero_width_removed += 1;
+                continue;
+            }
+        }
+
+        if options.bidi && BIDI_CONTROLS.contains(&c) {
+            changes.bidi_removed += 1;
+            continue;
+        }
+
+        if options.spaces {
+            let replacement = match c {
+                '\u{0085}' | '\u{2028}' | '\u{2029}' => Some('\n'),
+                c if c.is_whitespace() && !c.is_ascii() => Some(' '),
+                _ => None,
+            };
+
+            if let Some(replacement) = replacement {
+                text.push(replacement);
+                changes.spaces_replaced += 1;
+                continue;
+            }
+        }
+
+        if options.controls && c.is_control() && !matches!(c, '\t' | '\r' | '\n') {
+            changes.controls_removed += 1;
+            continue;
+        }
+
+        if options.noncharacters && noncharacters.contains(c) {
+            text.push(char::REPLACEMENT_CHARACTER);
+            changes.noncharacters_replaced += 1;
+            continue;
+        }
+
+        text.push(c);
+    }
+
+    if options.collapse_whitespace {
+        text = collapse_whitespace(&text, &mut changes.whitespace_collapsed);
+    }
+
+    if options.trim {
+        let trimmed = text.trim();
+        changes.trimmed = text.chars().count() - trimmed.chars().count();
+        text = trimmed.to_owned();
+    }
+
+    Sanitized { text, changes }
+}
+
+/// A lone tab is kept as it is, while longer runs become a single space.
+fn collapse_whitespace(src: &str, collapsed: &mut usize) -> String {
+    let mut result = String::with_capacity(src.len());
+    let mut run: Vec<char> = Vec::new();
+
+    let mut flush = |run: &mut Vec<char>, result: &mut String| {
+        match run[..] {
+            [] => {}
+            [c] => result.push(c),
+            _ => {
+                result.push(' ');
+                *collapsed += run.len() - 1;
+            }
+        }
+
+        run.clear();
+    };
+
+    for c in src.chars() {
+        if matches!(c, ' ' | '\t') {
+            run.push(c);
+            continue;
+        }
+
+        flush(&mut run, &mut result);
+        result.push(c);
+    }
+
+    flush(&mut run, &mut result);
+    result
+}
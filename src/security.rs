@@ -0,0 +1,285 @@
+use std::collections::HashMap;
+
+use icu_properties::props::Script;
+use icu_properties::{CodePointMapData, PropertyNamesLong};
+use serde::{Deserialize, Serialize};
+use unicode_normalization::UnicodeNormalization;
+use unicode_security::{MixedScript, RestrictionLevel, RestrictionLevelDetection};
+
+/// Characters with the `Bidi_Control` property.
+pub const BIDI_CONTROLS: [char; 12] = [
+    '\u{061C}', '\u{200E}', '\u{200F}', '\u{202A}', '\u{202B}', '\u{202C}', '\u{202D}', '\u{202E}',
+    '\u{2066}', '\u{2067}', '\u{2068}', '\u{2069}',
+];
+
+pub const ZERO_WIDTH: [char; 6] = [
+    '\u{180E}', '\u{200B}', '\u{200C}', '\u{200D}', '\u{2060}', '\u{FEFF}',
+];
+
+lazy_static! {
+    /// The scripts of the characters which are confusable with each prototype, i.e. whose
+    /// skeleton is the prototype. `unicode_security` only exposes the skeletons, so the
+    /// confusables data is inverted once, by taking the skeleton of every assigned character.
+    static ref PROTOTYPE_SCRIPTS: HashMap<String, Vec<Script>> = {
+        let scripts = CodePointMapData::<Script>::new();
+        let mut result: HashMap<String, Vec<Script>> = HashMap::new();
+        let mut buffer = [0u8; 4];
+
+        for c in (0..=u32::from(char::MAX)).filter_map(char::from_u32) {
+            let script = scripts.get(c);
+
+            if is_shared_script(script) {
+                continue;
+            }
+
+            let prototype = skeleton(c.encode_utf8(&mut buffer));
+
+            if prototype.chars().eq([c]) {
+                continue;
+            }
+
+            let confusable_scripts = result.entry(prototype).or_default();
+
+            if !confusable_scripts.contains(&script) {
+                confusable_scripts.push(script);
+            }
+        }
+
+        result
+    };
+}
+
+#[derive(Serialize, Debug)]
+pub struct SecurityReport {
+    /// The UTS #39 skeleton. Strings with equal skeletons are visually confusable.
+    pub skeleton: String,
+    /// The scripts of the text, ignoring characters which are common to many scripts.
+    pub scripts: Vec<&'static str>,
+    /// The text mixes scripts which aren't normally written together, e.g. Latin and Cyrillic.
+    pub mixed_script: bool,
+    /// The other scripts in which a string looks the same as this single-script text,
+    /// e.g. Cyrillic `сосо` can pass for Latin `coco`, and Latin `scope` for Cyrillic `ѕсоре`.
+    /// This is the UTS #39 whole-script confusable test.
+    pub whole_script_confusable: Vec<&'static str>,
+    pub restriction_level: &'static str,
+    pub bidi_controls: Vec<SuspiciousChar>,
+    /// Embeddings, overrides or isolates which are left open at the end of a line,
+    /// reordering whatever follows them. This is how "Trojan Source" attacks hide text.
+    pub unterminated_bidi: bool,
+    pub zero_width: Vec<SuspiciousChar>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct SuspiciousChar {
+    /// The UTF-8 byte offset of the character within the text.
+    pub offset: usize,
+    pub code_point: String,
+    pub name: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ConfusableRequest {
+    pub first: String,
+    pub second: String,
+}
+
+#[derive(Serialize, Debug)]
+pub struct Confusability {
+    pub confusable: bool,
+    /// The strings are the same code points, after NFC normalization.
+    pub identical: bool,
+    pub first_skeleton: String,
+    pub second_skeleton: String,
+}
+
+pub fn skeleton(src: &str) -> String {
+    unicode_security::skeleton(src).collect()
+}
+
+pub fn check(src: &str) -> SecurityReport {
+    let skeleton = skeleton(src);
+    let scripts = scripts_of(src);
+
+    SecurityReport {
+        scripts,
+        mixed_script: !src.is_single_script(),
+        whole_script_confusable: whole_script_confusable(src),
+        restriction_level: restriction_level_name(src.detect_restriction_level()),
+        bidi_controls: find_chars(src, &BIDI_CONTROLS),
+        unterminated_bidi: has_unterminated_bidi(src),
+        zero_width: find_chars(src, &ZERO_WIDTH),
+        skeleton,
+    }
+}
+
+pub fn compare(first: &str, second: &str) -> Confusability {
+    let first_skeleton = skeleton(first);
+    let second_skeleton = skeleton(second);
+
+    Confusability {
+        confusable: first_skeleton == second_skeleton,
+        identical: first.nfc().eq(second.nfc()),
+        first_skeleton,
+        second_skeleton,
+    }
+}
+
+fn scripts_of(src: &str) -> Vec<&'static str> {
+    let names = PropertyNamesLong::<Script>::new();
+
+    script_values(src)
+        .into_iter()
+        .filter_map(|script| names.get(script))
+        .collect()
+}
+
+/// The distinct scripts of the text, in order of appearance, ignoring the shared ones.
+fn script_values(src: &str) -> Vec<Script> {
+    let scripts = CodePointMapData::<Script>::new();
+
+    let mut result: Vec<Script> = Vec::new();
+
+    for script in src.chars().map(|c| scripts.get(c)) {
+        if !is_shared_script(script) && !result.contains(&script) {
+            result.push(script);
+        }
+    }
+
+    result
+}
+
+/// Characters of these scripts are used along with any other script.
+#[inline]
+fn is_shared_script(script: Script) -> bool {
+    matches!(script, Script::Common | Script::Inherited | Script::Unknown)
+}
+
+/// UTS #39 section 4: a single-script text has a whole-script confusable in another script
+/// when every one of its characters is confusable with some character of that script.
+fn whole_script_confusable(src: &str) -> Vec<&'static str> {
+    let [own_script] = script_values(src)[..] else {
+        return Vec::new();
+    };
+
+    let scripts = CodePointMapData::<Script>::new();
+    let names = PropertyNamesLong::<Script>::new();
+    let mut buffer = [0u8; 4];
+
+    // `None` until a character narrows it down.
+    let mut candidates: Option<Vec<Script>> = None;
+
+    for c in src.chars().filter(|c| !is_shared_script(scripts.get(*c))) {
+        let prototype = skeleton(c.encode_utf8(&mut buffer));
+
+        let mut confusable_scripts = script_values(&prototype);
+
+        // A prototype of shared characters, such as punctuation, fits within any script.
+        if confusable_scripts.is_empty() {
+            continue;
+        }
+
+        // A prototype mixing scripts isn't itself written in any single one.
+        if confusable_scripts.len() > 1 {
+            confusable_scripts.clear();
+        }
+
+        if let Some(others) = PROTOTYPE_SCRIPTS.get(&prototype) {
+            confusable_scripts.extend(others);
+        }
+
+        let narrowed = match candidates {
+            Some(candidates) => candidates
+                .into_iter()
+                .filter(|script| confusable_scripts.contains(script))
+                .collect(),
+            None => confusable_scripts,
+        };
+
+        candidates = Some(narrowed);
+    }
+
+    let mut result: Vec<&'static str> = candidates
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|script| *script != own_script)
+        .filter_map(|script| names.get(script))
+        .collect();
+
+    result.sort_unstable();
+    result.dedup();
+    result
+}
+
+fn restriction_level_name(level: RestrictionLevel) -> &'static str {
+    match level {
+        RestrictionLevel::ASCIIOnly => "ascii_only",
+        RestrictionLevel::SingleScript => "single_script",
+        RestrictionLevel::HighlyRestrictive => "highly_restrictive",
+        RestrictionLevel::ModeratelyRestrictive => "moderately_restrictive",
+        RestrictionLevel::MinimallyRestrictive => "minimally_restrictive",
+        RestrictionLevel::Unrestricted => "unrestricted",
+    }
+}
+
+fn find_chars(src: &str, chars: &[char]) -> Vec<SuspiciousChar> {
+    src.char_indices()
+        .filter(|(_, c)| chars.contains(c))
+        .map(|(offset, c)| SuspiciousChar {
+            offset,
+            code_point: format!("U+{:04X}", u32::from(c)),
+            name: unicode_names2::name(c).map(|name| name.to_string()),
+        })
+        .collect()
+}
+
+/// Bidi formatting is scoped to a paragraph, so anything left open at a line break
+/// has affected the rest of the line.
+fn has_unterminated_bidi(src: &str) -> bool {
+    // `true` for isolates, `false` for embeddings and overrides.
+    let mut open: Vec<bool> = Vec::new();
+
+    for c in src.chars() {
+        match c {
+            '\u{202A}' | '\u{202B}' | '\u{202D}' | '\u{202E}' => open.push(false),
+            '\u{2066}' | '\u{2067}' | '\u{2068}' => open.push(true),
+            '\u{202C}' if open.last() == Some(&false) => {
+                open.pop();
+            }
+            // Closing an isolate also closes the embeddings within it.
+            '\u{2069}' => {
+                if let Some(idx) = open.iter().rposition(|is_isolate| *is_isolate) {
+                    open.truncate(idx);
+                }
+            }
+            '\n' | '\r' | '\u{0085}' | '\u{2029}' if !open.is_empty() => return true,
+            _ => {}
+        }
+    }
+
+    !open.is_empty()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn whole_script_confusables() {
+        let confusable = whole_script_confusable("сосо");
+        assert!(confusable.contains(&"Latin") && confusable.contains(&"Greek"));
+        assert!(!confusable.contains(&"Cyrillic"));
+        assert!(whole_script_confusable("scope").contains(&"Cyrillic"));
+        assert!(whole_script_confusable("paypal").contains(&"Cyrillic"));
+    }
+
+    #[test]
+    fn no_whole_script_confusables() {
+        // Latin `m` has no Cyrillic look-alike.
+        assert!(!whole_script_confusable("paypal.com").contains(&"Cyrillic"));
+        // Nothing in Latin looks like Cyrillic `ж`.
+        assert!(!whole_script_confusable("жук").contains(&"Latin"));
+        // Mixed-script text has mixed-script confusables instead.
+        assert!(whole_script_confusable("pаypal").is_empty());
+        assert!(whole_script_confusable("1234 !?").is_empty());
+    }
+}
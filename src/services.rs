@@ -1,9 +1,37 @@
-use actix_web::{get, post, web, HttpResponse, Responder};
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use actix_web::{delete, get, post, put, web, HttpRequest, HttpResponse, Responder};
 use mailparse::parse_header;
 use serde::Deserialize;
 
+use crate::case;
+use crate::codecs;
+use crate::dictionary;
+use crate::dkim;
+use crate::escapes;
+use crate::html;
+use crate::inspect;
+use crate::language;
+use crate::layers;
+use crate::mail;
+use crate::mojibake;
+use crate::normalization;
+use crate::sanitize;
+use crate::security;
+use crate::similarity;
+use crate::stats;
+use crate::textops;
+use crate::tnef;
+use crate::tokenize;
+use crate::transliterate;
+use crate::urls;
 use crate::utils;
+use crate::whitespace;
+use crate::CFG;
 use crate::DEFAULT_CHARSET;
+use crate::DICTIONARIES;
 use crate::PATTERNS_CACHE;
 
 #[derive(Deserialize, Debug)]
@@ -11,6 +39,28 @@ pub struct RegexData {
     text: String,
     pattern: String,
     // join: String,
+    #[serde(default)]
+    normalize: Option<normalization::RegexNormalization>,
+}
+
+impl RegexData {
+    /// Returns the text and pattern, normalized when requested.
+    fn normalized(&self) -> (Cow<'_, str>, Cow<'_, str>) {
+        match &self.normalize {
+            Some(options) => (
+                Cow::Owned(options.text.normalize_text(&self.text)),
+                Cow::Owned(options.normalize_pattern(&self.pattern)),
+            ),
+            None => (Cow::Borrowed(&self.text), Cow::Borrowed(&self.pattern)),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct DkimData {
+    message: String,
+    /// `selector._domainkey.domain` -> TXT record value
+    keys: HashMap<String, String>,
 }
 
 #[get("/welcome")]
@@ -30,28 +80,166 @@ pub async fn echo(req_body: String) -> impl Responder {
 // TODO: Add HTML playground for the API
 
 #[post("/unescape")]
-pub async fn unescape(req_body: String) -> impl Responder {
-    let unescaped_req_body =
-        utils::unescape_as_bytes(&req_body).expect("Unable to unescape request's body.");
+pub async fn unescape(
+    options: web::Query<escapes::UnescapeOptions>,
+    layout: web::Query<whitespace::WhitespaceOptions>,
+    req_body: String,
+) -> impl Responder {
+    unescape_response(&req_body, DEFAULT_CHARSET, &options, &layout)
+}
 
-    let response = utils::attempt_decode(&unescaped_req_body, DEFAULT_CHARSET).unwrap();
+#[post("/unescape/{charset}")]
+pub async fn unescape_charset(
+    path: web::Path<(String,)>,
+    options: web::Query<escapes::UnescapeOptions>,
+    layout: web::Query<whitespace::WhitespaceOptions>,
+    req_body: String,
+) -> impl Responder {
+    let (charset,) = path.into_inner();
 
-    HttpResponse::Ok().body(response.into_owned())
+    unescape_response(&req_body, &charset, &options, &layout)
 }
 
-#[post("/unescape/{charset}")]
-pub async fn unescape_charset(path: web::Path<(String,)>, req_body: String) -> impl Responder {
+fn unescape_response(
+    req_body: &str,
+    charset: &str,
+    options: &escapes::UnescapeOptions,
+    layout: &whitespace::WhitespaceOptions,
+) -> HttpResponse {
+    let unescaped_req_body = match options.style {
+        Some(style) => match escapes::unescape(req_body, style) {
+            Ok(unescaped) => unescaped,
+            Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+        },
+        None => utils::unescape_as_bytes(req_body).expect("Unable to unescape request's body."),
+    };
+
+    let response = utils::attempt_decode(&unescaped_req_body, charset).unwrap();
+
+    HttpResponse::Ok().body(whitespace::finish(&response, layout).into_owned())
+}
+
+#[post("/escape")]
+pub async fn escape(
+    options: web::Query<escapes::EscapeOptions>,
+    req_body: String,
+) -> impl Responder {
+    HttpResponse::Ok().body(escapes::escape(&req_body, options.style, options.ascii))
+}
+
+#[post("/decode_url")]
+pub async fn decode_url(
+    options: web::Query<urls::UrlDecodeOptions>,
+    layout: web::Query<whitespace::WhitespaceOptions>,
+    req_body: String,
+) -> impl Responder {
+    let response = urls::percent_decode(&req_body, DEFAULT_CHARSET, &options);
+
+    HttpResponse::Ok().body(whitespace::finish(&response, &layout).into_owned())
+}
+
+#[post("/decode_url/{charset}")]
+pub async fn decode_url_charset(
+    path: web::Path<(String,)>,
+    options: web::Query<urls::UrlDecodeOptions>,
+    layout: web::Query<whitespace::WhitespaceOptions>,
+    req_body: String,
+) -> impl Responder {
+    let (charset,) = path.into_inner();
+    let response = urls::percent_decode(&req_body, &charset, &options);
+
+    HttpResponse::Ok().body(whitespace::finish(&response, &layout).into_owned())
+}
+
+#[post("/encode_url")]
+pub async fn encode_url(
+    options: web::Query<urls::UrlEncodeOptions>,
+    req_body: String,
+) -> impl Responder {
+    HttpResponse::Ok().body(urls::percent_encode(&req_body, options.mode))
+}
+
+#[post("/parse_url")]
+pub async fn parse_url(
+    options: web::Query<urls::UrlDecodeOptions>,
+    req_body: String,
+) -> impl Responder {
+    parse_url_response(&req_body, DEFAULT_CHARSET, &options)
+}
+
+#[post("/parse_url/{charset}")]
+pub async fn parse_url_charset(
+    path: web::Path<(String,)>,
+    options: web::Query<urls::UrlDecodeOptions>,
+    req_body: String,
+) -> impl Responder {
+    let (charset,) = path.into_inner();
+
+    parse_url_response(&req_body, &charset, &options)
+}
+
+fn parse_url_response(
+    req_body: &str,
+    charset: &str,
+    options: &urls::UrlDecodeOptions,
+) -> HttpResponse {
+    match urls::parse_url(req_body, charset, options) {
+        Ok(parsed) => HttpResponse::Ok().json(parsed),
+        Err(e) => HttpResponse::BadRequest().body(e.to_string()),
+    }
+}
+
+#[post("/decode_entities")]
+pub async fn decode_entities(
+    layout: web::Query<whitespace::WhitespaceOptions>,
+    req_body: String,
+) -> impl Responder {
+    let response = html::decode_entities(&req_body);
+
+    HttpResponse::Ok().body(whitespace::finish(&response, &layout).into_owned())
+}
+
+#[post("/encode_entities")]
+pub async fn encode_entities(
+    options: web::Query<html::EntityEncodeOptions>,
+    req_body: String,
+) -> impl Responder {
+    HttpResponse::Ok().body(html::encode_entities(&req_body, options.mode).into_owned())
+}
+
+#[post("/decode_layers")]
+pub async fn decode_layers(
+    options: web::Query<layers::LayersOptions>,
+    req_body: web::Bytes,
+) -> impl Responder {
+    HttpResponse::Ok().json(layers::decode_layers(
+        &req_body,
+        DEFAULT_CHARSET,
+        options.depth,
+    ))
+}
+
+#[post("/decode_layers/{charset}")]
+pub async fn decode_layers_charset(
+    path: web::Path<(String,)>,
+    options: web::Query<layers::LayersOptions>,
+    req_body: web::Bytes,
+) -> impl Responder {
     let (charset,) = path.into_inner();
-    let unescaped_req_body =
-        utils::unescape_as_bytes(&req_body).expect("Unable to unescape request's body.");
 
-    let response = utils::attempt_decode(&unescaped_req_body, &charset).unwrap();
+    HttpResponse::Ok().json(layers::decode_layers(&req_body, &charset, options.depth))
+}
 
-    HttpResponse::Ok().body(response.into_owned())
+#[post("/fix_mojibake")]
+pub async fn fix_mojibake(req_body: String) -> impl Responder {
+    HttpResponse::Ok().json(mojibake::fix_mojibake(&req_body))
 }
 
 #[post("/decode_quoted_printable")]
-pub async fn decode_quoted_printable(req_body: String) -> impl Responder {
+pub async fn decode_quoted_printable(
+    layout: web::Query<whitespace::WhitespaceOptions>,
+    req_body: String,
+) -> impl Responder {
     // let response = match quoted_printable::decode(&req_body, quoted_printable::ParseMode::Robust) {
     //     Ok(v) => {
     //         utils::attempt_decode(&v, &DEFAULT_CHARSET).unwrap()
@@ -63,49 +251,231 @@ pub async fn decode_quoted_printable(req_body: String) -> impl Responder {
 
     let response = utils::decode_quoted_printable(&req_body, DEFAULT_CHARSET).unwrap();
 
-    HttpResponse::Ok().body(response.into_owned())
+    HttpResponse::Ok().body(whitespace::finish(&response, &layout).into_owned())
 }
 
 #[post("/decode_quoted_printable/{charset}")]
 pub async fn decode_quoted_printable_charset(
     path: web::Path<(String,)>,
+    layout: web::Query<whitespace::WhitespaceOptions>,
     req_body: String,
 ) -> impl Responder {
     let (charset,) = path.into_inner();
-    let response = match quoted_printable::decode(&req_body, quoted_printable::ParseMode::Robust) {
-        Ok(v) => v,
-        Err(_) => return HttpResponse::Ok().body(req_body),
+    let Ok(response) = quoted_printable::decode(&req_body, quoted_printable::ParseMode::Robust)
+    else {
+        return HttpResponse::Ok().body(whitespace::finish(&req_body, &layout).into_owned());
     };
 
     let response = utils::attempt_decode(&response, &charset).unwrap();
 
-    HttpResponse::Ok().body(response.into_owned())
+    HttpResponse::Ok().body(whitespace::finish(&response, &layout).into_owned())
+}
+
+#[post("/html_to_text")]
+pub async fn html_to_text(
+    options: web::Query<html::HtmlToTextOptions>,
+    layout: web::Query<whitespace::WhitespaceOptions>,
+    req_body: web::Bytes,
+) -> impl Responder {
+    let src = utils::attempt_decode(&req_body, DEFAULT_CHARSET).unwrap();
+
+    html_to_text_response(&src, &options, &layout)
+}
+
+#[post("/html_to_text/{charset}")]
+pub async fn html_to_text_charset(
+    path: web::Path<(String,)>,
+    options: web::Query<html::HtmlToTextOptions>,
+    layout: web::Query<whitespace::WhitespaceOptions>,
+    req_body: web::Bytes,
+) -> impl Responder {
+    let (charset,) = path.into_inner();
+    let src = utils::attempt_decode(&req_body, &charset).unwrap();
+
+    html_to_text_response(&src, &options, &layout)
+}
+
+fn html_to_text_response(
+    src: &str,
+    options: &html::HtmlToTextOptions,
+    layout: &whitespace::WhitespaceOptions,
+) -> HttpResponse {
+    let mut response = html::html_to_text(src);
+    response.text = whitespace::finish(&response.text, layout).into_owned();
+
+    if options.links {
+        HttpResponse::Ok().json(response)
+    } else {
+        HttpResponse::Ok().body(response.text)
+    }
 }
 
 #[post("/decode_base64")]
-pub async fn decode_base64(req_body: String) -> impl Responder {
+pub async fn decode_base64(
+    layout: web::Query<whitespace::WhitespaceOptions>,
+    req_body: String,
+) -> impl Responder {
     let raw_payload = base64::decode(&req_body).expect("Unable to decode base64.");
 
     let response = utils::attempt_decode(&raw_payload, DEFAULT_CHARSET).unwrap();
 
-    HttpResponse::Ok().body(response.into_owned())
+    HttpResponse::Ok().body(whitespace::finish(&response, &layout).into_owned())
 }
 
 #[post("/decode_base64/{charset}")]
-pub async fn decode_base64_charset(path: web::Path<(String,)>, req_body: String) -> impl Responder {
+pub async fn decode_base64_charset(
+    path: web::Path<(String,)>,
+    layout: web::Query<whitespace::WhitespaceOptions>,
+    req_body: String,
+) -> impl Responder {
     let (charset,) = path.into_inner();
     let raw_payload = base64::decode(&req_body).expect("Unable to decode base64.");
 
     let response = utils::attempt_decode(&raw_payload, &charset).unwrap();
 
-    HttpResponse::Ok().body(response.into_owned())
+    HttpResponse::Ok().body(whitespace::finish(&response, &layout).into_owned())
+}
+
+#[post("/decode_uuencode")]
+pub async fn decode_uuencode(
+    options: web::Query<codecs::DecodeOptions>,
+    layout: web::Query<whitespace::WhitespaceOptions>,
+    req_body: String,
+) -> impl Responder {
+    decoded_file_response(
+        codecs::decode_uuencode(&req_body),
+        DEFAULT_CHARSET,
+        &options,
+        &layout,
+    )
+}
+
+#[post("/decode_uuencode/{charset}")]
+pub async fn decode_uuencode_charset(
+    path: web::Path<(String,)>,
+    options: web::Query<codecs::DecodeOptions>,
+    layout: web::Query<whitespace::WhitespaceOptions>,
+    req_body: String,
+) -> impl Responder {
+    let (charset,) = path.into_inner();
+
+    decoded_file_response(
+        codecs::decode_uuencode(&req_body),
+        &charset,
+        &options,
+        &layout,
+    )
+}
+
+#[post("/encode_uuencode")]
+pub async fn encode_uuencode(
+    options: web::Query<codecs::UuencodeOptions>,
+    req_body: web::Bytes,
+) -> impl Responder {
+    match codecs::encode_uuencode(&req_body, &options.name, &options.mode) {
+        Ok(response) => HttpResponse::Ok().body(response),
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
+
+#[post("/decode_yenc")]
+pub async fn decode_yenc(
+    options: web::Query<codecs::DecodeOptions>,
+    layout: web::Query<whitespace::WhitespaceOptions>,
+    req_body: web::Bytes,
+) -> impl Responder {
+    decoded_file_response(
+        codecs::decode_yenc(&req_body),
+        DEFAULT_CHARSET,
+        &options,
+        &layout,
+    )
+}
+
+#[post("/decode_yenc/{charset}")]
+pub async fn decode_yenc_charset(
+    path: web::Path<(String,)>,
+    options: web::Query<codecs::DecodeOptions>,
+    layout: web::Query<whitespace::WhitespaceOptions>,
+    req_body: web::Bytes,
+) -> impl Responder {
+    let (charset,) = path.into_inner();
+
+    decoded_file_response(codecs::decode_yenc(&req_body), &charset, &options, &layout)
+}
+
+#[post("/decode_binhex")]
+pub async fn decode_binhex(
+    options: web::Query<codecs::DecodeOptions>,
+    layout: web::Query<whitespace::WhitespaceOptions>,
+    req_body: String,
+) -> impl Responder {
+    decoded_file_response(
+        codecs::decode_binhex(&req_body),
+        DEFAULT_CHARSET,
+        &options,
+        &layout,
+    )
+}
+
+#[post("/decode_binhex/{charset}")]
+pub async fn decode_binhex_charset(
+    path: web::Path<(String,)>,
+    options: web::Query<codecs::DecodeOptions>,
+    layout: web::Query<whitespace::WhitespaceOptions>,
+    req_body: String,
+) -> impl Responder {
+    let (charset,) = path.into_inner();
+
+    decoded_file_response(
+        codecs::decode_binhex(&req_body),
+        &charset,
+        &options,
+        &layout,
+    )
+}
+
+#[post("/decode_tnef")]
+pub async fn decode_tnef(
+    options: web::Query<tnef::TnefOptions>,
+    req_body: web::Bytes,
+) -> impl Responder {
+    match tnef::decode_tnef_blob(&req_body, options.data) {
+        Ok(contents) => HttpResponse::Ok().json(contents),
+        Err(e) => HttpResponse::BadRequest().body(e.to_string()),
+    }
+}
+
+fn decoded_file_response(
+    decoded_file: Result<codecs::DecodedFile, utils::DecodingError>,
+    charset: &str,
+    options: &codecs::DecodeOptions,
+    layout: &whitespace::WhitespaceOptions,
+) -> HttpResponse {
+    let decoded_file = match decoded_file {
+        Ok(decoded_file) => decoded_file,
+        Err(e) => return HttpResponse::BadRequest().body(e.to_string()),
+    };
+
+    if options.raw {
+        return HttpResponse::Ok()
+            .content_type("application/octet-stream")
+            .body(decoded_file.data);
+    }
+
+    let response = utils::attempt_decode(&decoded_file.data, charset).unwrap();
+
+    HttpResponse::Ok().body(whitespace::finish(&response, layout).into_owned())
 }
 
 #[post("/decode_mime_header")]
-pub async fn decode_mime_header(req_body: String) -> impl Responder {
-    let normalized_req_body = utils::normalize_str(&req_body);
+pub async fn decode_mime_header(
+    layout: web::Query<whitespace::WhitespaceOptions>,
+    req_body: String,
+) -> impl Responder {
+    let prepared_req_body = whitespace::prepare(&req_body, &layout);
 
-    // let response: String = normalized_req_body.lines()
+    // let response: String = prepared_req_body.lines()
     //     .map(|x| {
     //         let prefixed_x = format!(":{x}");
     //         let (parsed, _) = parse_header(prefixed_x.as_bytes()).unwrap();
@@ -115,40 +485,403 @@ pub async fn decode_mime_header(req_body: String) -> impl Responder {
     //     .map(|x| utils::attempt_decode(&x, &DEFAULT_ENCODING).unwrap())
     //     .collect();
 
-    let response = utils::decode_mime_header(&normalized_req_body).unwrap();
+    let response = utils::decode_mime_header(&prepared_req_body).unwrap();
 
-    HttpResponse::Ok().body(response.into_owned())
+    HttpResponse::Ok().body(whitespace::finish(&response, &layout).into_owned())
 }
 
 #[post("/decode_mime_header/rfc822")]
-pub async fn decode_mime_header_rfc822(req_body: web::Bytes) -> impl Responder {
+pub async fn decode_mime_header_rfc822(
+    layout: web::Query<whitespace::WhitespaceOptions>,
+    req_body: web::Bytes,
+) -> impl Responder {
     let (parsed, _) = parse_header(&req_body).unwrap();
 
-    HttpResponse::Ok().body(parsed.get_value())
+    HttpResponse::Ok().body(whitespace::finish(&parsed.get_value(), &layout).into_owned())
 }
 
-#[post("/decode_auto")]
-pub async fn decode_auto(req_body: String) -> impl Responder {
-    let response = utils::auto_decode(&req_body, DEFAULT_CHARSET).unwrap();
+#[post("/parse_addresses")]
+pub async fn parse_addresses(
+    options: web::Query<mail::AddressOptions>,
+    req_body: String,
+) -> impl Responder {
+    match mail::parse_addresses(&req_body, &options) {
+        Ok(addresses) => HttpResponse::Ok().json(addresses),
+        Err(e) => HttpResponse::BadRequest().body(e.to_string()),
+    }
+}
 
-    HttpResponse::Ok().body(response.into_owned())
+#[post("/received_hops")]
+pub async fn received_hops(req_body: web::Bytes) -> impl Responder {
+    match mail::analyze_received_hops(&req_body) {
+        Ok(hops) => HttpResponse::Ok().json(hops),
+        Err(e) => HttpResponse::BadRequest().body(e.to_string()),
+    }
+}
+
+#[post("/auth_results")]
+pub async fn auth_results(req_body: web::Bytes) -> impl Responder {
+    match mail::parse_auth_results(&req_body) {
+        Ok(results) => HttpResponse::Ok().json(results),
+        Err(e) => HttpResponse::BadRequest().body(e.to_string()),
+    }
+}
+
+#[post("/verify_dkim")]
+pub async fn verify_dkim(request: web::Json<DkimData>) -> impl Responder {
+    let response = dkim::verify_message(&request.message, &request.keys);
+
+    HttpResponse::Ok().json(response)
+}
+
+#[post("/decode_auto")]
+pub async fn decode_auto(
+    layout: web::Query<whitespace::WhitespaceOptions>,
+    req_body: web::Bytes,
+) -> impl Responder {
+    auto_decode_response(&req_body, DEFAULT_CHARSET, &layout)
 }
 
 #[post("/decode_auto/{charset}")]
-pub async fn decode_auto_charset(path: web::Path<(String,)>, req_body: String) -> impl Responder {
+pub async fn decode_auto_charset(
+    path: web::Path<(String,)>,
+    layout: web::Query<whitespace::WhitespaceOptions>,
+    req_body: web::Bytes,
+) -> impl Responder {
     let (charset,) = path.into_inner();
-    let response = utils::auto_decode(&req_body, &charset).unwrap();
 
-    HttpResponse::Ok().body(response.into_owned())
+    auto_decode_response(&req_body, &charset, &layout)
+}
+
+fn auto_decode_response(
+    req_body: &[u8],
+    charset: &str,
+    layout: &whitespace::WhitespaceOptions,
+) -> HttpResponse {
+    // Input normalization only applies to text. Binary input, such as yEnc, is passed as is.
+    let prepared_req_body =
+        std::str::from_utf8(req_body).map(|req_body| whitespace::prepare(req_body, layout));
+
+    let src = match &prepared_req_body {
+        Ok(prepared) => prepared.as_bytes(),
+        Err(_) => req_body,
+    };
+
+    match utils::auto_decode_bytes(src, charset) {
+        Ok(response) => HttpResponse::Ok().body(whitespace::finish(&response, layout).into_owned()),
+        Err(e) => HttpResponse::BadRequest().body(e.to_string()),
+    }
+}
+
+#[post("/normalize/{form}")]
+pub async fn normalize(
+    path: web::Path<(String,)>,
+    options: web::Query<normalization::NormalizeOptions>,
+    req_body: String,
+) -> impl Responder {
+    let (form,) = path.into_inner();
+
+    match form.parse() {
+        Ok(form) => HttpResponse::Ok().body(normalization::normalize(&req_body, form, &options)),
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
+
+#[post("/case/{mode}")]
+pub async fn convert_case(
+    path: web::Path<(String,)>,
+    options: web::Query<case::CaseOptions>,
+    req_body: String,
+) -> impl Responder {
+    let (mode,) = path.into_inner();
+
+    let mode = match mode.parse() {
+        Ok(mode) => mode,
+        Err(e) => return HttpResponse::BadRequest().body(e),
+    };
+
+    match case::convert_case(&req_body, mode, &options) {
+        Ok(response) => HttpResponse::Ok().body(response),
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
+
+#[post("/transliterate/{scheme}")]
+pub async fn transliterate_text(path: web::Path<(String,)>, req_body: String) -> impl Responder {
+    let (scheme,) = path.into_inner();
+
+    match scheme.parse() {
+        Ok(scheme) => HttpResponse::Ok().body(transliterate::transliterate(&req_body, scheme)),
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
+
+#[post("/inspect")]
+pub async fn inspect_text(req_body: String) -> impl Responder {
+    HttpResponse::Ok().json(inspect::inspect(&req_body))
+}
+
+#[post("/detect_language")]
+pub async fn detect_language(
+    req_body: String,
+    options: web::Query<language::LanguageOptions>,
+) -> impl Responder {
+    HttpResponse::Ok().json(language::detect(&req_body, options.limit))
+}
+
+#[post("/security_check")]
+pub async fn security_check(req_body: String) -> impl Responder {
+    HttpResponse::Ok().json(security::check(&req_body))
+}
+
+#[post("/confusable")]
+pub async fn confusable(request: web::Json<security::ConfusableRequest>) -> impl Responder {
+    HttpResponse::Ok().json(security::compare(&request.first, &request.second))
+}
+
+#[post("/similarity")]
+pub async fn compare_similarity(
+    request: web::Json<similarity::SimilarityRequest>,
+) -> impl Responder {
+    let request = request.into_inner();
+
+    // The edit distances take quadratic time, so they're kept off the worker.
+    match web::block(move || similarity::similarity(&request)).await {
+        Ok(Ok(result)) => HttpResponse::Ok().json(result),
+        Ok(Err(e)) => HttpResponse::BadRequest().body(e),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+#[post("/fuzzy_search")]
+pub async fn fuzzy_search(request: web::Json<similarity::FuzzySearchRequest>) -> impl Responder {
+    let request = request.into_inner();
+
+    match web::block(move || similarity::fuzzy_search(&request)).await {
+        Ok(Ok(matches)) => HttpResponse::Ok().json(matches),
+        Ok(Err(e)) => HttpResponse::BadRequest().body(e),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+#[get("/dictionaries")]
+pub async fn list_dictionaries() -> impl Responder {
+    let mut dictionaries: Vec<dictionary::DictionaryInfo> = DICTIONARIES
+        .read()
+        .iter()
+        .map(|(name, dictionary)| dictionary::DictionaryInfo {
+            name: name.clone(),
+            terms: dictionary.term_count(),
+        })
+        .collect();
+
+    dictionaries.sort_by(|a, b| a.name.cmp(&b.name));
+
+    HttpResponse::Ok().json(dictionaries)
+}
+
+#[post("/dictionaries/{name}/search")]
+pub async fn search_dictionary(
+    name: web::Path<String>,
+    req_body: String,
+    options: web::Query<dictionary::SearchOptions>,
+) -> impl Responder {
+    // Searches outside of the lock, so a long search doesn't hold up an upload.
+    let Some(dictionary) = DICTIONARIES.read().get(name.as_str()).cloned() else {
+        return HttpResponse::NotFound().body(format!("Unknown dictionary: {name}"));
+    };
+
+    // A large dictionary over a large text takes a while, so the search is kept off the worker.
+    let options = options.into_inner();
+
+    match web::block(move || dictionary.search(&req_body, &options)).await {
+        Ok(Ok(matches)) => HttpResponse::Ok().json(matches),
+        Ok(Err(e)) => HttpResponse::BadRequest().body(e),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+/// Replaces the dictionary, or adds it. Uploads are kept in memory only.
+#[put("/dictionaries/{name}")]
+pub async fn upload_dictionary(
+    req: HttpRequest,
+    name: web::Path<String>,
+    payload: web::Payload,
+) -> impl Responder {
+    if let Some(rejection) = reject_non_admin(&req) {
+        return rejection;
+    }
+
+    let body = match payload
+        .to_bytes_limited(CFG.dictionaries.max_upload_size)
+        .await
+    {
+        Ok(Ok(body)) => body,
+        Ok(Err(e)) => return HttpResponse::BadRequest().body(e.to_string()),
+        Err(e) => return HttpResponse::PayloadTooLarge().body(e.to_string()),
+    };
+
+    // Building the automata of a large dictionary takes a while, so it's kept off the worker.
+    let parsed = web::block(move || {
+        let contents = std::str::from_utf8(&body)
+            .map_err(|_| "A dictionary must be valid UTF-8".to_owned())?;
+        dictionary::Dictionary::parse(contents)
+    })
+    .await;
+
+    let dictionary = match parsed {
+        Ok(Ok(dictionary)) => dictionary,
+        Ok(Err(e)) => return HttpResponse::BadRequest().body(e),
+        Err(e) => return HttpResponse::InternalServerError().body(e.to_string()),
+    };
+
+    let info = dictionary::DictionaryInfo {
+        name: name.into_inner(),
+        terms: dictionary.term_count(),
+    };
+
+    log::info!("Dictionary `{}`: Uploaded {} terms", info.name, info.terms);
+
+    DICTIONARIES
+        .write()
+        .insert(info.name.clone(), std::sync::Arc::new(dictionary));
+
+    HttpResponse::Ok().json(info)
+}
+
+#[delete("/dictionaries/{name}")]
+pub async fn delete_dictionary(req: HttpRequest, name: web::Path<String>) -> impl Responder {
+    if let Some(rejection) = reject_non_admin(&req) {
+        return rejection;
+    }
+
+    match DICTIONARIES.write().remove(name.as_str()) {
+        Some(_) => {
+            log::info!("Dictionary `{name}`: Deleted");
+            HttpResponse::NoContent().finish()
+        }
+        None => HttpResponse::NotFound().body(format!("Unknown dictionary: {name}")),
+    }
+}
+
+/// Expects `Authorization: Bearer <admin_token>`, with the token from `cfg.toml`.
+/// Returns the response to reject the request with, otherwise.
+fn reject_non_admin(req: &HttpRequest) -> Option<HttpResponse> {
+    // An empty token would let anyone in with a bare `Bearer `, so it counts as unset.
+    let Some(admin_token) = CFG
+        .dictionaries
+        .admin_token
+        .as_deref()
+        .filter(|token| !token.trim().is_empty())
+    else {
+        return Some(HttpResponse::Forbidden()
+            .body("Dictionary uploads are disabled. Set `admin_token` under `[dictionaries]` in `cfg.toml`"));
+    };
+
+    let token = req
+        .headers()
+        .get("Authorization")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split_once(' '))
+        // Authentication schemes are case-insensitive (RFC 7235)
+        .filter(|(scheme, _)| scheme.eq_ignore_ascii_case("Bearer"))
+        .map(|(_, token)| token);
+
+    match token {
+        Some(token) if constant_time_eq(token.as_bytes(), admin_token.as_bytes()) => None,
+        _ => Some(HttpResponse::Unauthorized().body("Invalid or missing admin token")),
+    }
+}
+
+/// Compares without stopping at the first difference, so the time taken doesn't reveal the token.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+#[post("/sanitize")]
+pub async fn sanitize_text(
+    options: web::Query<sanitize::SanitizeOptions>,
+    req_body: web::Bytes,
+) -> impl Responder {
+    HttpResponse::Ok().json(sanitize::sanitize(&req_body, &options))
+}
+
+#[post("/reverse")]
+pub async fn reverse(req_body: String) -> impl Responder {
+    HttpResponse::Ok().body(textops::reverse(&req_body))
+}
+
+#[post("/length")]
+pub async fn length(req_body: String) -> impl Responder {
+    HttpResponse::Ok().json(textops::length(&req_body))
+}
+
+#[post("/stats")]
+pub async fn text_stats(
+    req_body: String,
+    options: web::Query<stats::StatsOptions>,
+) -> impl Responder {
+    HttpResponse::Ok().json(stats::stats(&req_body, &options))
+}
+
+#[post("/words")]
+pub async fn words(
+    req_body: String,
+    options: web::Query<tokenize::TokenizeOptions>,
+) -> impl Responder {
+    match tokenize::words(&req_body, &options) {
+        Ok(words) => HttpResponse::Ok().json(words),
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
+
+#[post("/sentences")]
+pub async fn sentences(
+    req_body: String,
+    options: web::Query<tokenize::TokenizeOptions>,
+) -> impl Responder {
+    HttpResponse::Ok().json(tokenize::sentences(&req_body, &options))
+}
+
+#[post("/truncate")]
+pub async fn truncate(
+    options: web::Query<textops::TruncateOptions>,
+    req_body: String,
+) -> impl Responder {
+    match textops::truncate(&req_body, &options) {
+        Ok(response) => HttpResponse::Ok().body(response),
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
+
+#[post("/substring")]
+pub async fn substring(
+    options: web::Query<textops::SubstringOptions>,
+    req_body: String,
+) -> impl Responder {
+    match textops::substring(&req_body, &options) {
+        Ok(response) => HttpResponse::Ok().body(response),
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
+}
+
+#[post("/pad")]
+pub async fn pad(options: web::Query<textops::PadOptions>, req_body: String) -> impl Responder {
+    match textops::pad(&req_body, &options) {
+        Ok(response) => HttpResponse::Ok().body(response),
+        Err(e) => HttpResponse::BadRequest().body(e),
+    }
 }
 
 #[post("/regex_capture_group")]
 pub async fn regex_capture_group(request: web::Json<RegexData>) -> impl Responder {
+    let (text, pattern) = request.normalized();
+
     let mut patterns_cache = PATTERNS_CACHE.write();
 
-    let re = patterns_cache.get(&request.pattern);
+    let re = patterns_cache.get(&pattern);
 
-    let caps = re.captures(&request.text).unwrap();
+    let caps = re.captures(&text).unwrap();
 
     let response = caps.get(1).unwrap().as_str().to_owned();
 
@@ -161,17 +894,19 @@ pub async fn regex_to_json(request: web::Json<RegexData>) -> impl Responder {
     // TODO: Consider how to use `read` on RWLock and `write` only when needed (maybe move sync stuff into the PatternsCache?)
     // let mut patterns_cache = PATTERNS_CACHE.read();
 
+    let (text, pattern) = request.normalized();
+
     let mut patterns_cache = PATTERNS_CACHE.write();
 
-    let re = patterns_cache.get(&request.pattern);
+    let re = patterns_cache.get(&pattern);
 
-    let caps = re.captures(&request.text).unwrap();
+    let caps = re.captures(&text).unwrap();
 
     let mut response = String::from('{');
 
     for key in re.capture_names().flatten() {
         if let Some(value) = caps.name(key) {
-            response.push_str(&format!("\"{}\":\"{}\",", key, value.as_str()));
+            let _ = write!(response, "\"{}\":\"{}\",", key, value.as_str());
         }
     }
 
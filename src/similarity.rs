@@ -0,0 +1,294 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+
+use serde::{Deserialize, Serialize};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::normalization::TextNormalization;
+use crate::CFG;
+
+#[derive(Deserialize, Debug)]
+pub struct SimilarityRequest {
+    pub first: String,
+    pub second: String,
+
+    /// The size of the character n-grams compared by `jaccard` and `cosine`.
+    #[serde(default = "default_ngram")]
+    pub ngram: usize,
+
+    #[serde(default)]
+    pub normalize: Option<TextNormalization>,
+}
+
+#[inline]
+fn default_ngram() -> usize {
+    2
+}
+
+#[derive(Serialize, Debug)]
+pub struct Similarity {
+    pub levenshtein: usize,
+    /// Levenshtein distance scaled into a similarity, between 0 and 1.
+    pub normalized_levenshtein: f64,
+    /// Levenshtein distance which also counts a swap of two adjacent characters as a single edit.
+    pub damerau_levenshtein: usize,
+    pub jaro_winkler: f64,
+    pub jaccard: f64,
+    pub cosine: f64,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct FuzzySearchRequest {
+    pub needle: String,
+    pub haystack: String,
+
+    /// The most edits an occurrence may differ by.
+    /// Defaults to a quarter of the needle's length.
+    pub max_distance: Option<usize>,
+
+    #[serde(default)]
+    pub normalize: Option<TextNormalization>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct FuzzyMatch {
+    /// The occurrence as it appears in the haystack, before normalization.
+    pub text: String,
+    /// The UTF-8 byte offsets of the occurrence within the haystack.
+    pub start: usize,
+    pub end: usize,
+    pub distance: usize,
+}
+
+/// Distances count characters, after normalization when requested.
+pub fn similarity(request: &SimilarityRequest) -> Result<Similarity, String> {
+    if request.ngram == 0 {
+        return Err("The `ngram` size must be at least 1".to_owned());
+    }
+
+    check_length("first", &request.first)?;
+    check_length("second", &request.second)?;
+
+    let first = normalized(&request.first, request.normalize.as_ref());
+    let second = normalized(&request.second, request.normalize.as_ref());
+
+    let first_ngrams = ngrams(&first, request.ngram);
+    let second_ngrams = ngrams(&second, request.ngram);
+
+    Ok(Similarity {
+        levenshtein: strsim::levenshtein(&first, &second),
+        normalized_levenshtein: strsim::normalized_levenshtein(&first, &second),
+        damerau_levenshtein: strsim::damerau_levenshtein(&first, &second),
+        jaro_winkler: strsim::jaro_winkler(&first, &second),
+        jaccard: jaccard(&first_ngrams, &second_ngrams),
+        cosine: cosine(&first_ngrams, &second_ngrams),
+    })
+}
+
+/// The edit distances take time in proportion to the product of both lengths.
+fn check_length(field: &str, src: &str) -> Result<(), String> {
+    let max_length = CFG.similarity.max_length;
+
+    // Every character takes at least a byte, so only long texts need counting.
+    if src.len() > max_length && src.chars().count() > max_length {
+        return Err(format!(
+            "`{field}` is longer than the maximum of {max_length} characters"
+        ));
+    }
+
+    Ok(())
+}
+
+#[inline]
+fn normalized<'src>(src: &'src str, normalize: Option<&TextNormalization>) -> Cow<'src, str> {
+    match normalize {
+        Some(normalize) => Cow::Owned(normalize.normalize_text(src)),
+        None => Cow::Borrowed(src),
+    }
+}
+
+/// Counts the character n-grams. Text shorter than `n` is a single n-gram of its own.
+fn ngrams(src: &str, n: usize) -> HashMap<String, usize> {
+    let chars: Vec<char> = src.chars().collect();
+    let mut result: HashMap<String, usize> = HashMap::new();
+
+    if chars.is_empty() {
+        return result;
+    }
+
+    if chars.len() < n {
+        result.insert(src.to_owned(), 1);
+        return result;
+    }
+
+    for window in chars.windows(n) {
+        *result.entry(window.iter().collect()).or_default() += 1;
+    }
+
+    result
+}
+
+/// Two empty texts are identical.
+#[allow(clippy::cast_precision_loss)]
+fn jaccard(first: &HashMap<String, usize>, second: &HashMap<String, usize>) -> f64 {
+    let first: HashSet<&String> = first.keys().collect();
+    let second: HashSet<&String> = second.keys().collect();
+
+    let union = first.union(&second).count();
+
+    if union == 0 {
+        return 1.0;
+    }
+
+    first.intersection(&second).count() as f64 / union as f64
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn cosine(first: &HashMap<String, usize>, second: &HashMap<String, usize>) -> f64 {
+    if first.is_empty() && second.is_empty() {
+        return 1.0;
+    }
+
+    let dot: usize = first
+        .iter()
+        .filter_map(|(ngram, count)| second.get(ngram).map(|other| count * other))
+        .sum();
+
+    let magnitude = |ngrams: &HashMap<String, usize>| {
+        (ngrams.values().map(|count| count * count).sum::<usize>() as f64).sqrt()
+    };
+
+    let magnitudes = magnitude(first) * magnitude(second);
+
+    if magnitudes == 0.0 {
+        return 0.0;
+    }
+
+    // Rounding can push identical texts slightly above 1.
+    (dot as f64 / magnitudes).min(1.0)
+}
+
+/// Finds the occurrences of the needle within `max_distance` edits, best first,
+/// and returns the ones which don't overlap a better one, in the order they appear.
+pub fn fuzzy_search(request: &FuzzySearchRequest) -> Result<Vec<FuzzyMatch>, String> {
+    check_length("needle", &request.needle)?;
+    check_length("haystack", &request.haystack)?;
+
+    let needle: Vec<char> = normalized(&request.needle, request.normalize.as_ref())
+        .chars()
+        .collect();
+
+    if needle.is_empty() {
+        return Err("The needle is empty".to_owned());
+    }
+
+    let max_distance = request.max_distance.unwrap_or(needle.len() / 4);
+
+    if max_distance >= needle.len() {
+        return Err(format!(
+            "`max_distance` must be smaller than the needle's length, which is {}",
+            needle.len()
+        ));
+    }
+
+    let haystack = haystack_chars(&request.haystack, request.normalize.as_ref());
+    let mut candidates = approximate_occurrences(&needle, &haystack, max_distance);
+
+    candidates.sort_by_key(|&(start, end, distance)| {
+        (distance, (end - start).abs_diff(needle.len()), start)
+    });
+
+    let mut selected: Vec<(usize, usize, usize)> = Vec::new();
+
+    for candidate in candidates {
+        let (start, end, _) = candidate;
+
+        if selected
+            .iter()
+            .all(|&(other_start, other_end, _)| end <= other_start || start >= other_end)
+        {
+            selected.push(candidate);
+        }
+    }
+
+    selected.sort_unstable();
+
+    Ok(selected
+        .into_iter()
+        .map(|(start, end, distance)| {
+            let start = haystack[start].1;
+            let end = haystack[end - 1].2;
+
+            FuzzyMatch {
+                text: request.haystack[start..end].to_owned(),
+                start,
+                end,
+                distance,
+            }
+        })
+        .collect())
+}
+
+/// The characters of the haystack, each with the byte range of the grapheme cluster it came from.
+/// Normalizing one cluster at a time keeps the offsets pointing into the original text.
+fn haystack_chars(src: &str, normalize: Option<&TextNormalization>) -> Vec<(char, usize, usize)> {
+    src.grapheme_indices(true)
+        .flat_map(|(offset, grapheme)| {
+            let end = offset + grapheme.len();
+
+            normalized(grapheme, normalize)
+                .chars()
+                .map(|c| (c, offset, end))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Sellers' algorithm, which is Levenshtein distance where the match may start anywhere in the haystack.
+/// Returns every `(start, end, distance)` character range within `max_distance`, one for each end.
+fn approximate_occurrences(
+    needle: &[char],
+    haystack: &[(char, usize, usize)],
+    max_distance: usize,
+) -> Vec<(usize, usize, usize)> {
+    let mut occurrences = Vec::new();
+
+    // The distance of each needle prefix, and where in the haystack that alignment starts.
+    // The next column is written into a second pair of buffers, which then swap places.
+    let mut distances: Vec<usize> = (0..=needle.len()).collect();
+    let mut starts: Vec<usize> = vec![0; needle.len() + 1];
+    let mut next_distances: Vec<usize> = vec![0; needle.len() + 1];
+    let mut next_starts: Vec<usize> = vec![0; needle.len() + 1];
+
+    for (idx, &(c, _, _)) in haystack.iter().enumerate() {
+        // An empty needle prefix matches anywhere, starting right after this character.
+        next_distances[0] = 0;
+        next_starts[0] = idx + 1;
+
+        for i in 1..=needle.len() {
+            let substitution = distances[i - 1] + usize::from(needle[i - 1] != c);
+            let skip_needle = next_distances[i - 1] + 1;
+            let skip_haystack = distances[i] + 1;
+
+            (next_distances[i], next_starts[i]) =
+                if substitution <= skip_needle && substitution <= skip_haystack {
+                    (substitution, starts[i - 1])
+                } else if skip_needle <= skip_haystack {
+                    (skip_needle, next_starts[i - 1])
+                } else {
+                    (skip_haystack, starts[i])
+                };
+        }
+
+        std::mem::swap(&mut distances, &mut next_distances);
+        std::mem::swap(&mut starts, &mut next_starts);
+
+        let distance = distances[needle.len()];
+
+        if distance <= max_distance {
+            occurrences.push((starts[needle.len()], idx + 1, distance));
+        }
+    }
+
+    occurrences
+}
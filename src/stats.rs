@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+
+use icu_properties::props::{GeneralCategory, GeneralCategoryGroup};
+use icu_properties::CodePointMapData;
+use serde::{Deserialize, Serialize};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::language::{self, ScriptShare};
+use crate::textops::{self, TextLength};
+
+#[derive(Deserialize, Debug)]
+pub struct StatsOptions {
+    /// How many of the most frequent tokens to return.
+    #[serde(default = "default_top")]
+    pub top: usize,
+}
+
+#[inline]
+fn default_top() -> usize {
+    10
+}
+
+#[derive(Serialize, Debug)]
+pub struct TextStats {
+    #[serde(flatten)]
+    pub length: TextLength,
+    pub words: usize,
+    pub lines: usize,
+    pub sentences: usize,
+    pub classes: CharClasses,
+    /// The share of the characters outside of ASCII, between 0 and 1.
+    pub non_ascii_ratio: f64,
+    pub scripts: Vec<ScriptShare>,
+    /// The Shannon entropy of the bytes, in bits per byte. Plain text is usually around 4 to 5,
+    /// long Base64 close to 6, while compressed or encrypted data approaches 8. Short texts score lower.
+    pub entropy: f64,
+    /// In characters.
+    pub average_word_length: f64,
+    pub top_tokens: Vec<TokenCount>,
+}
+
+/// Characters by their Unicode general category.
+#[derive(Serialize, Debug, Default)]
+pub struct CharClasses {
+    pub letters: usize,
+    pub digits: usize,
+    pub punctuation: usize,
+    pub symbols: usize,
+    pub whitespace: usize,
+    pub other: usize,
+    pub non_ascii: usize,
+}
+
+#[derive(Serialize, Debug)]
+pub struct TokenCount {
+    pub token: String,
+    pub count: usize,
+}
+
+#[allow(clippy::cast_precision_loss)]
+pub fn stats(src: &str, options: &StatsOptions) -> TextStats {
+    let length = textops::length(src);
+    let words: Vec<&str> = src.unicode_words().collect();
+
+    let word_chars: usize = words.iter().map(|word| word.chars().count()).sum();
+    let average_word_length = if words.is_empty() {
+        0.0
+    } else {
+        word_chars as f64 / words.len() as f64
+    };
+
+    let classes = char_classes(src);
+
+    let non_ascii_ratio = if length.chars == 0 {
+        0.0
+    } else {
+        classes.non_ascii as f64 / length.chars as f64
+    };
+
+    TextStats {
+        words: words.len(),
+        lines: src.lines().count(),
+        sentences: src
+            .unicode_sentences()
+            .filter(|sentence| !sentence.trim().is_empty())
+            .count(),
+        non_ascii_ratio,
+        classes,
+        scripts: language::script_shares(src),
+        entropy: entropy(src.as_bytes()),
+        average_word_length,
+        top_tokens: top_tokens(&words, options.top),
+        length,
+    }
+}
+
+fn char_classes(src: &str) -> CharClasses {
+    let categories = CodePointMapData::<GeneralCategory>::new();
+    let mut classes = CharClasses::default();
+
+    for c in src.chars() {
+        let category = categories.get(c);
+
+        if c.is_whitespace() {
+            classes.whitespace += 1;
+        } else if GeneralCategoryGroup::Letter.contains(category) {
+            classes.letters += 1;
+        } else if category == GeneralCategory::DecimalNumber {
+            classes.digits += 1;
+        } else if GeneralCategoryGroup::Punctuation.contains(category) {
+            classes.punctuation += 1;
+        } else if GeneralCategoryGroup::Symbol.contains(category) {
+            classes.symbols += 1;
+        } else {
+            classes.other += 1;
+        }
+
+        if !c.is_ascii() {
+            classes.non_ascii += 1;
+        }
+    }
+
+    classes
+}
+
+#[allow(clippy::cast_precision_loss)]
+fn entropy(src: &[u8]) -> f64 {
+    let mut counts = [0_usize; 256];
+
+    for &byte in src {
+        counts[usize::from(byte)] += 1;
+    }
+
+    let total = src.len() as f64;
+
+    counts
+        .iter()
+        .filter(|&&count| count > 0)
+        .map(|&count| {
+            let p = count as f64 / total;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+/// Tokens are counted regardless of case. Ties are ordered alphabetically.
+fn top_tokens(words: &[&str], top: usize) -> Vec<TokenCount> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+
+    for word in words {
+        *counts.entry(word.to_lowercase()).or_default() += 1;
+    }
+
+    let mut counts: Vec<(String, usize)> = counts.into_iter().collect();
+    counts.sort_by(|(a_token, a_count), (b_token, b_count)| {
+        b_count.cmp(a_count).then_with(|| a_token.cmp(b_token))
+    });
+
+    counts
+        .into_iter()
+        .take(top)
+        .map(|(token, count)| TokenCount { token, count })
+        .collect()
+}
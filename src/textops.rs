@@ -0,0 +1,162 @@
+use serde::{Deserialize, Serialize};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+#[derive(Serialize, Debug)]
+pub struct TextLength {
+    pub bytes: usize,
+    pub chars: usize,
+    pub graphemes: usize,
+    /// Terminal columns, where East Asian wide characters and most emoji take two.
+    pub width: usize,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct TruncateOptions {
+    /// The maximum number of grapheme clusters to keep.
+    pub graphemes: Option<usize>,
+    /// The maximum number of UTF-8 bytes to keep.
+    pub bytes: Option<usize>,
+    /// Appended when the text was cut, counting towards the limit.
+    pub ellipsis: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct SubstringOptions {
+    /// The first grapheme cluster, counted from 0.
+    #[serde(default)]
+    pub start: usize,
+    /// The grapheme cluster to stop before. Defaults to the end of the text.
+    pub end: Option<usize>,
+}
+
+#[derive(Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Align {
+    #[default]
+    Left,
+    Right,
+    Center,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct PadOptions {
+    /// The display width to pad to. Text which is already as wide is returned as it is.
+    /// At most `MAX_PAD_COLUMNS`, or `MAX_PAD_FACTOR` times the text's own width.
+    pub width: usize,
+
+    #[serde(default)]
+    pub align: Align,
+
+    /// Must be a single column wide.
+    #[serde(default = "default_fill")]
+    pub fill: char,
+}
+
+/// Bounds the padded width, so a short request can't build an arbitrarily long response.
+const MAX_PAD_COLUMNS: usize = 1024;
+const MAX_PAD_FACTOR: usize = 16;
+
+#[inline]
+fn default_fill() -> char {
+    ' '
+}
+
+/// Reverses by grapheme cluster, so combining marks, emoji sequences and flags stay intact.
+pub fn reverse(src: &str) -> String {
+    src.graphemes(true).rev().collect()
+}
+
+pub fn length(src: &str) -> TextLength {
+    TextLength {
+        bytes: src.len(),
+        chars: src.chars().count(),
+        graphemes: src.graphemes(true).count(),
+        width: src.width(),
+    }
+}
+
+/// Cuts the text at a grapheme cluster boundary, within the given limits.
+pub fn truncate(src: &str, options: &TruncateOptions) -> Result<String, String> {
+    if options.graphemes.is_none() && options.bytes.is_none() {
+        return Err("Expected a `graphemes` or a `bytes` limit".to_owned());
+    }
+
+    let max_graphemes = options.graphemes.unwrap_or(usize::MAX);
+    let max_bytes = options.bytes.unwrap_or(usize::MAX);
+
+    let fits =
+        |text: &str| text.graphemes(true).count() <= max_graphemes && text.len() <= max_bytes;
+
+    if fits(src) {
+        return Ok(src.to_owned());
+    }
+
+    let ellipsis = options.ellipsis.as_deref().unwrap_or_default();
+
+    if !fits(ellipsis) {
+        return Err("The ellipsis alone exceeds the limit".to_owned());
+    }
+
+    let max_graphemes = max_graphemes - ellipsis.graphemes(true).count();
+    let max_bytes = max_bytes - ellipsis.len();
+
+    let end = src
+        .grapheme_indices(true)
+        .take(max_graphemes)
+        .take_while(|(offset, grapheme)| offset + grapheme.len() <= max_bytes)
+        .last()
+        .map_or(0, |(offset, grapheme)| offset + grapheme.len());
+
+    Ok(format!("{}{ellipsis}", &src[..end]))
+}
+
+pub fn substring(src: &str, options: &SubstringOptions) -> Result<String, String> {
+    let end = options.end.unwrap_or(usize::MAX);
+
+    if end < options.start {
+        return Err(format!(
+            "The end ({end}) is before the start ({})",
+            options.start
+        ));
+    }
+
+    Ok(src
+        .graphemes(true)
+        .skip(options.start)
+        .take(end - options.start)
+        .collect())
+}
+
+pub fn pad(src: &str, options: &PadOptions) -> Result<String, String> {
+    let mut buffer = [0u8; 4];
+
+    if options.fill.encode_utf8(&mut buffer).width() != 1 {
+        return Err(format!(
+            "The fill character must be a single column wide, got: {:?}",
+            options.fill
+        ));
+    }
+
+    let width = src.width();
+    let max_width = MAX_PAD_COLUMNS.max(width.saturating_mul(MAX_PAD_FACTOR));
+
+    if options.width > max_width {
+        return Err(format!(
+            "The width ({}) exceeds the maximum of {max_width} for this text",
+            options.width
+        ));
+    }
+
+    let missing = options.width.saturating_sub(width);
+
+    let (left, right) = match options.align {
+        Align::Left => (0, missing),
+        Align::Right => (missing, 0),
+        Align::Center => (missing / 2, missing - missing / 2),
+    };
+
+    let fill = |count: usize| std::iter::repeat_n(options.fill, count);
+
+    Ok(fill(left).chain(src.chars()).chain(fill(right)).collect())
+}
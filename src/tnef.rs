@@ -0,0 +1,606 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::utils::{self, DecodingError};
+
+const TNEF_SIGNATURE: u32 = 0x223E_9F78;
+
+const LEVEL_MESSAGE: u8 = 1;
+const LEVEL_ATTACHMENT: u8 = 2;
+
+// TNEF attributes
+const ATT_BODY: u32 = 0x0002_800C;
+const ATT_ATTACH_DATA: u32 = 0x0006_800F;
+const ATT_ATTACH_TITLE: u32 = 0x0001_8010;
+const ATT_ATTACH_REND_DATA: u32 = 0x0006_9002;
+const ATT_MAPI_PROPS: u32 = 0x0006_9003;
+const ATT_ATTACHMENT: u32 = 0x0006_9005;
+const ATT_OEM_CODEPAGE: u32 = 0x0006_9007;
+
+// MAPI properties
+const PR_BODY: u16 = 0x1000;
+const PR_RTF_COMPRESSED: u16 = 0x1009;
+const PR_HTML: u16 = 0x1013;
+const PR_ATTACH_DATA: u16 = 0x3701;
+const PR_ATTACH_FILENAME: u16 = 0x3704;
+const PR_ATTACH_LONG_FILENAME: u16 = 0x3707;
+const PR_ATTACH_MIME_TAG: u16 = 0x370E;
+
+// MAPI property types
+const PT_MULTIPLE_VALUES: u16 = 0x1000;
+const PT_OBJECT: u16 = 0x000D;
+const PT_STRING8: u16 = 0x001E;
+const PT_UNICODE: u16 = 0x001F;
+const PT_BINARY: u16 = 0x0102;
+
+/// Compressed RTF (MS-OXRTFCP) dictionary is initialized with this string.
+const RTF_PRESET_DICTIONARY: &[u8] = b"{\\rtf1\\ansi\\mac\\deff0\\deftab720{\\fonttbl;}{\\f0\\fnil \\froman \\fswiss \\fmodern \\fscript \\fdecor MS Sans SerifSymbolArialTimes New RomanCourier{\\colortbl\\red0\\green0\\blue0\r\n\\par \\pard\\plain\\f0\\fs20\\b\\i\\u\\tab\\tx";
+const RTF_DICTIONARY_SIZE: usize = 4096;
+const RTF_COMPRESSED: u32 = 0x7546_5A4C; // "LZFu"
+const RTF_UNCOMPRESSED: u32 = 0x414C_454D; // "MELA"
+
+#[derive(Deserialize, Debug, Default)]
+pub struct TnefOptions {
+    /// Includes the base64 encoded bytes of every attachment.
+    #[serde(default)]
+    pub data: bool,
+}
+
+#[derive(Serialize, Debug, Default)]
+pub struct TnefContents {
+    pub attachments: Vec<TnefAttachment>,
+    pub text_body: Option<String>,
+    pub html_body: Option<String>,
+    pub rtf_body: Option<String>,
+    /// Parts which couldn't be decoded, and were left out rather than failing the rest.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+#[derive(Serialize, Debug)]
+pub struct TnefAttachment {
+    pub name: Option<String>,
+    pub mime_type: Option<String>,
+    pub size: usize,
+    pub sha256: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<String>,
+}
+
+enum MapiValue {
+    Binary(Vec<u8>),
+    String8(Vec<u8>),
+    Unicode(String),
+    Other,
+}
+
+type MapiProps = HashMap<u16, MapiValue>;
+
+#[derive(Default)]
+struct RawAttachment {
+    title: Option<Vec<u8>>,
+    data: Option<Vec<u8>>,
+    props: MapiProps,
+}
+
+struct Reader<'a> {
+    src: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(src: &'a [u8]) -> Self {
+        Self { src, pos: 0 }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pos >= self.src.len()
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], DecodingError> {
+        let bytes = self
+            .pos
+            .checked_add(len)
+            .and_then(|end| self.src.get(self.pos..end))
+            .ok_or("Truncated TNEF data")?;
+
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, DecodingError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, DecodingError> {
+        let bytes = self.bytes(2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, DecodingError> {
+        let bytes = self.bytes(4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn len(&mut self) -> Result<usize, DecodingError> {
+        Ok(self.u32()? as usize)
+    }
+
+    /// MAPI values are padded to a multiple of 4 bytes.
+    fn padded_bytes(&mut self, len: usize) -> Result<&'a [u8], DecodingError> {
+        let bytes = self.bytes(len)?;
+        self.bytes((4 - len % 4) % 4)?;
+        Ok(bytes)
+    }
+}
+
+/// Takes a TNEF blob, either raw or base64 encoded.
+pub fn decode_tnef_blob(src: &[u8], include_data: bool) -> Result<TnefContents, DecodingError> {
+    if src.starts_with(&TNEF_SIGNATURE.to_le_bytes()) {
+        return decode_tnef(src, include_data);
+    }
+
+    let encoded: Vec<u8> = src
+        .iter()
+        .copied()
+        .filter(|b| !b.is_ascii_whitespace())
+        .collect();
+
+    let decoded = base64::decode(encoded).map_err(|_| "Not a TNEF blob")?;
+
+    decode_tnef(&decoded, include_data)
+}
+
+/// The attributes of a TNEF stream, before their strings are decoded.
+#[derive(Default)]
+struct RawTnef {
+    attachments: Vec<RawAttachment>,
+    message_props: MapiProps,
+    text_body: Option<Vec<u8>>,
+    codepage: Option<u32>,
+}
+
+fn read_attributes(src: &[u8]) -> Result<RawTnef, DecodingError> {
+    let mut reader = Reader::new(src);
+
+    if reader.u32()? != TNEF_SIGNATURE {
+        return Err("Invalid TNEF signature".into());
+    }
+
+    let _key = reader.u16()?;
+
+    let mut raw = RawTnef::default();
+
+    while !reader.is_empty() {
+        let level = reader.u8()?;
+        let attribute = reader.u32()?;
+        let len = reader.len()?;
+        let data = reader.bytes(len)?;
+        let _checksum = reader.u16()?;
+
+        // Every attachment starts with its rendering attribute.
+        if attribute == ATT_ATTACH_REND_DATA
+            || (level == LEVEL_ATTACHMENT && raw.attachments.is_empty())
+        {
+            raw.attachments.push(RawAttachment::default());
+        }
+
+        match (level, raw.attachments.last_mut()) {
+            (LEVEL_ATTACHMENT, Some(attachment)) => match attribute {
+                ATT_ATTACH_TITLE => attachment.title = Some(data.to_vec()),
+                ATT_ATTACH_DATA => attachment.data = Some(data.to_vec()),
+                ATT_ATTACHMENT => attachment.props = parse_mapi_props(data)?,
+                _ => {}
+            },
+            (LEVEL_MESSAGE, _) => match attribute {
+                ATT_MAPI_PROPS => raw.message_props = parse_mapi_props(data)?,
+                ATT_BODY => raw.text_body = Some(data.to_vec()),
+                ATT_OEM_CODEPAGE => raw.codepage = Reader::new(data).u32().ok(),
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+
+    Ok(raw)
+}
+
+/// Extracts the attachments and bodies out of a TNEF (`application/ms-tnef`, winmail.dat) blob.
+pub fn decode_tnef(src: &[u8], include_data: bool) -> Result<TnefContents, DecodingError> {
+    let RawTnef {
+        attachments,
+        message_props,
+        text_body,
+        codepage,
+    } = read_attributes(src)?;
+
+    let charset = codepage.map_or_else(|| "windows-1252".to_owned(), codepage_to_charset);
+
+    let decode_string = |value: &MapiValue| -> Option<String> {
+        match value {
+            MapiValue::String8(bytes) | MapiValue::Binary(bytes) => {
+                let bytes = trim_nul(bytes);
+                Some(utils::attempt_decode(bytes, &charset).ok()?.into_owned())
+            }
+            MapiValue::Unicode(s) => Some(s.clone()),
+            MapiValue::Other => None,
+        }
+    };
+
+    let mut result = TnefContents {
+        text_body: text_body
+            .and_then(|body| decode_string(&MapiValue::String8(body)))
+            .or_else(|| message_props.get(&PR_BODY).and_then(decode_string)),
+        html_body: message_props.get(&PR_HTML).and_then(decode_string),
+        ..TnefContents::default()
+    };
+
+    if let Some(MapiValue::Binary(compressed)) = message_props.get(&PR_RTF_COMPRESSED) {
+        let rtf_body = decompress_rtf(compressed)
+            .and_then(|rtf| Ok(utils::attempt_decode(&rtf, &charset)?.into_owned()));
+
+        match rtf_body {
+            Ok(rtf_body) => result.rtf_body = Some(rtf_body),
+            Err(e) => result.warnings.push(format!("RTF body: {e}")),
+        }
+    }
+
+    for attachment in attachments {
+        let data = attachment
+            .data
+            .or_else(|| match attachment.props.get(&PR_ATTACH_DATA) {
+                Some(MapiValue::Binary(data)) => Some(data.clone()),
+                _ => None,
+            })
+            .unwrap_or_default();
+
+        let name = attachment
+            .props
+            .get(&PR_ATTACH_LONG_FILENAME)
+            .or_else(|| attachment.props.get(&PR_ATTACH_FILENAME))
+            .and_then(decode_string)
+            .or_else(|| {
+                attachment
+                    .title
+                    .as_deref()
+                    .and_then(|title| decode_string(&MapiValue::String8(title.to_vec())))
+            });
+
+        let sha256 = Sha256::digest(&data)
+            .iter()
+            .fold(String::with_capacity(64), |mut hex, b| {
+                let _ = write!(hex, "{b:02x}");
+                hex
+            });
+
+        result.attachments.push(TnefAttachment {
+            name,
+            mime_type: attachment
+                .props
+                .get(&PR_ATTACH_MIME_TAG)
+                .and_then(decode_string),
+            size: data.len(),
+            sha256,
+            data: include_data.then(|| base64::encode(&data)),
+        });
+    }
+
+    Ok(result)
+}
+
+fn parse_mapi_props(src: &[u8]) -> Result<MapiProps, DecodingError> {
+    let mut reader = Reader::new(src);
+    let mut props = MapiProps::new();
+
+    let count = reader.u32()?;
+
+    for _ in 0..count {
+        let prop_type = reader.u16()?;
+        let prop_id = reader.u16()?;
+
+        // Named properties carry their GUID and either a numeric ID or a name.
+        if prop_id >= 0x8000 {
+            reader.bytes(16)?;
+
+            if reader.u32()? == 0 {
+                reader.u32()?;
+            } else {
+                let name_len = reader.len()?;
+                reader.padded_bytes(name_len)?;
+            }
+        }
+
+        let base_type = prop_type & !PT_MULTIPLE_VALUES;
+        let is_variable = matches!(base_type, PT_OBJECT | PT_STRING8 | PT_UNICODE | PT_BINARY);
+
+        let values_count = if prop_type & PT_MULTIPLE_VALUES != 0 || is_variable {
+            reader.u32()?
+        } else {
+            1
+        };
+
+        for idx in 0..values_count {
+            let value = read_mapi_value(&mut reader, base_type)?;
+
+            if idx == 0 {
+                props.entry(prop_id).or_insert(value);
+            }
+        }
+    }
+
+    Ok(props)
+}
+
+fn read_mapi_value(reader: &mut Reader, prop_type: u16) -> Result<MapiValue, DecodingError> {
+    let value = match prop_type {
+        // Null, Short, Long, Float, Error, Boolean
+        0x0001 | 0x0002 | 0x0003 | 0x0004 | 0x000A | 0x000B => {
+            reader.bytes(4)?;
+            MapiValue::Other
+        }
+        // Double, Currency, Application time, 64 bit integer, System time
+        0x0005 | 0x0006 | 0x0007 | 0x0014 | 0x0040 => {
+            reader.bytes(8)?;
+            MapiValue::Other
+        }
+        // CLSID
+        0x0048 => {
+            reader.bytes(16)?;
+            MapiValue::Other
+        }
+        PT_STRING8 => {
+            let len = reader.len()?;
+            MapiValue::String8(reader.padded_bytes(len)?.to_vec())
+        }
+        PT_UNICODE => {
+            let len = reader.len()?;
+            let utf16: Vec<u16> = reader
+                .padded_bytes(len)?
+                .chunks_exact(2)
+                .map(|c| u16::from_le_bytes([c[0], c[1]]))
+                .collect();
+
+            MapiValue::Unicode(
+                String::from_utf16_lossy(&utf16)
+                    .trim_end_matches('\0')
+                    .to_owned(),
+            )
+        }
+        PT_BINARY => {
+            let len = reader.len()?;
+            MapiValue::Binary(reader.padded_bytes(len)?.to_vec())
+        }
+        // Objects (e.g. embedded messages) start with a 16 bytes interface identifier.
+        PT_OBJECT => {
+            let len = reader.len()?;
+            let bytes = reader.padded_bytes(len)?;
+            MapiValue::Binary(bytes.get(16..).unwrap_or_default().to_vec())
+        }
+        _ => return Err(format!("Unsupported MAPI property type: 0x{prop_type:04X}").into()),
+    };
+
+    Ok(value)
+}
+
+/// Decompresses the `PR_RTF_COMPRESSED` property, as described in MS-OXRTFCP.
+pub fn decompress_rtf(src: &[u8]) -> Result<Vec<u8>, DecodingError> {
+    let mut reader = Reader::new(src);
+
+    let _compressed_size = reader.u32()?;
+    let raw_size = reader.len()?;
+    let compression_type = reader.u32()?;
+    let _crc = reader.u32()?;
+
+    let contents = &src[reader.pos..];
+
+    if compression_type == RTF_UNCOMPRESSED {
+        return Ok(contents[..raw_size.min(contents.len())].to_vec());
+    }
+
+    if compression_type != RTF_COMPRESSED {
+        return Err("Unknown compressed RTF type".into());
+    }
+
+    let mut dictionary = [0u8; RTF_DICTIONARY_SIZE];
+    dictionary[..RTF_PRESET_DICTIONARY.len()].copy_from_slice(RTF_PRESET_DICTIONARY);
+
+    let mut write_pos = RTF_PRESET_DICTIONARY.len();
+    // `raw_size` comes from the sender, so it only hints at the capacity as far as the input can back it.
+    let mut result = Vec::with_capacity(raw_size.min(contents.len().saturating_mul(8)));
+    let mut bytes = contents.iter();
+    let truncated = || DecodingError::from("Truncated compressed RTF");
+
+    loop {
+        let &control = bytes.next().ok_or_else(truncated)?;
+
+        for bit in 0..8 {
+            if control & (1 << bit) == 0 {
+                let &literal = bytes.next().ok_or_else(truncated)?;
+
+                result.push(literal);
+                dictionary[write_pos] = literal;
+                write_pos = (write_pos + 1) % RTF_DICTIONARY_SIZE;
+                continue;
+            }
+
+            let (Some(&high), Some(&low)) = (bytes.next(), bytes.next()) else {
+                return Err(truncated());
+            };
+
+            let reference = u16::from_be_bytes([high, low]);
+            let offset = usize::from(reference >> 4);
+            let length = usize::from(reference & 0x0F) + 2;
+
+            // A reference to the current write position marks the end of the data.
+            if offset == write_pos {
+                return Ok(result);
+            }
+
+            for idx in 0..length {
+                let b = dictionary[(offset + idx) % RTF_DICTIONARY_SIZE];
+                result.push(b);
+                dictionary[write_pos] = b;
+                write_pos = (write_pos + 1) % RTF_DICTIONARY_SIZE;
+            }
+        }
+    }
+}
+
+/// Translates a Windows code page number into a charset name known by `utils::decode_bytes`.
+fn codepage_to_charset(codepage: u32) -> String {
+    match codepage {
+        65001 => "utf-8".into(),
+        20127 => "us-ascii".into(),
+        20866 => "koi8-r".into(),
+        21866 => "koi8-u".into(),
+        10000 => "macintosh".into(),
+        28591..=28606 => format!("iso-8859-{}", codepage - 28590),
+        _ => format!("cp{codepage}"),
+    }
+}
+
+fn trim_nul(src: &[u8]) -> &[u8] {
+    let end = src.iter().rposition(|b| *b != 0).map_or(0, |idx| idx + 1);
+    &src[..end]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn hex(src: &str) -> Vec<u8> {
+        src.split_whitespace()
+            .map(|b| u8::from_str_radix(b, 16).unwrap())
+            .collect()
+    }
+
+    fn attribute(level: u8, id: u32, data: &[u8]) -> Vec<u8> {
+        let mut result = vec![level];
+        result.extend_from_slice(&id.to_le_bytes());
+        result.extend_from_slice(&u32::try_from(data.len()).unwrap().to_le_bytes());
+        result.extend_from_slice(data);
+        result.extend_from_slice(&[0, 0]);
+        result
+    }
+
+    fn binary_prop(id: u16, value: &[u8]) -> Vec<u8> {
+        let mut result = 1u32.to_le_bytes().to_vec();
+        result.extend_from_slice(&PT_BINARY.to_le_bytes());
+        result.extend_from_slice(&id.to_le_bytes());
+        result.extend_from_slice(&1u32.to_le_bytes());
+        result.extend_from_slice(&u32::try_from(value.len()).unwrap().to_le_bytes());
+        result.extend_from_slice(value);
+        result.resize(result.len() + (4 - value.len() % 4) % 4, 0);
+        result
+    }
+
+    fn tnef(attributes: &[Vec<u8>]) -> Vec<u8> {
+        let mut result = TNEF_SIGNATURE.to_le_bytes().to_vec();
+        result.extend_from_slice(&[0x01, 0x00]);
+        result.extend(attributes.concat());
+        result
+    }
+
+    /// MS-OXRTFCP 3.1.1
+    #[test]
+    fn decompress_rtf_simple() {
+        let compressed = hex(
+            "2d 00 00 00 2b 00 00 00 4c 5a 46 75 f1 c5 c7 a7 03 00 0a 00 72 63 70 67 31 32 35 42 32 0a f3 \
+            20 68 65 6c 09 00 20 62 77 05 b0 6c 64 7d 0a 80 0f a0",
+        );
+
+        assert_eq!(
+            decompress_rtf(&compressed).unwrap(),
+            b"{\\rtf1\\ansi\\ansicpg1252\\pard hello world}\r\n"
+        );
+    }
+
+    /// MS-OXRTFCP 3.1.2, where a reference overlaps the bytes it's writing.
+    #[test]
+    fn decompress_rtf_crossing_the_write_position() {
+        let compressed = hex(
+            "1a 00 00 00 1c 00 00 00 4c 5a 46 75 e2 d4 4b 51 41 00 04 20 57 58 59 5a 0d 6e 7d 01 0e b0",
+        );
+
+        assert_eq!(
+            decompress_rtf(&compressed).unwrap(),
+            b"{\\rtf1 WXYZWXYZWXYZWXYZWXYZ}"
+        );
+    }
+
+    #[test]
+    fn decompress_rtf_uncompressed() {
+        let mut uncompressed = hex("13 00 00 00 07 00 00 00 4d 45 4c 41 00 00 00 00");
+        uncompressed.extend_from_slice(b"{\\rtf1}\0");
+
+        assert_eq!(decompress_rtf(&uncompressed).unwrap(), b"{\\rtf1}");
+    }
+
+    #[test]
+    fn decompress_rtf_truncated() {
+        let compressed = hex(
+            "2d 00 00 00 2b 00 00 00 4c 5a 46 75 f1 c5 c7 a7 03 00 0a 00 72 63 70 67 31 32 35 42 32 0a f3",
+        );
+
+        assert!(decompress_rtf(&compressed).is_err());
+        assert!(decompress_rtf(&compressed[..10]).is_err());
+    }
+
+    #[test]
+    fn a_corrupt_rtf_body_keeps_the_attachments() {
+        let blob = tnef(&[
+            attribute(
+                LEVEL_MESSAGE,
+                ATT_MAPI_PROPS,
+                &binary_prop(
+                    PR_RTF_COMPRESSED,
+                    &hex("2d 00 00 00 2b 00 00 00 4c 5a 46 75 f1 c5"),
+                ),
+            ),
+            attribute(LEVEL_ATTACHMENT, ATT_ATTACH_REND_DATA, &[0; 14]),
+            attribute(LEVEL_ATTACHMENT, ATT_ATTACH_TITLE, b"notes.txt\0"),
+            attribute(LEVEL_ATTACHMENT, ATT_ATTACH_DATA, b"hello"),
+        ]);
+
+        let contents = decode_tnef_blob(&blob, true).unwrap();
+
+        assert_eq!(contents.rtf_body, None);
+        assert_eq!(contents.warnings.len(), 1);
+        assert_eq!(contents.attachments.len(), 1);
+        assert_eq!(contents.attachments[0].name.as_deref(), Some("notes.txt"));
+        assert_eq!(contents.attachments[0].size, 5);
+        assert_eq!(contents.attachments[0].data.as_deref(), Some("aGVsbG8="));
+    }
+
+    #[test]
+    fn attachment_names_come_from_the_mapi_props() {
+        let blob = tnef(&[
+            attribute(LEVEL_MESSAGE, ATT_BODY, b"Hi there\0"),
+            attribute(LEVEL_ATTACHMENT, ATT_ATTACH_REND_DATA, &[0; 14]),
+            attribute(LEVEL_ATTACHMENT, ATT_ATTACH_TITLE, b"REPORT~1.PDF\0"),
+            attribute(LEVEL_ATTACHMENT, ATT_ATTACH_DATA, b"%PDF"),
+            attribute(
+                LEVEL_ATTACHMENT,
+                ATT_ATTACHMENT,
+                &binary_prop(PR_ATTACH_LONG_FILENAME, b"Quarterly report.pdf\0"),
+            ),
+            attribute(LEVEL_ATTACHMENT, ATT_ATTACH_REND_DATA, &[0; 14]),
+            attribute(LEVEL_ATTACHMENT, ATT_ATTACH_DATA, b""),
+        ]);
+
+        let contents = decode_tnef(&blob, false).unwrap();
+
+        assert_eq!(contents.text_body.as_deref(), Some("Hi there"));
+        assert_eq!(contents.attachments.len(), 2);
+        assert_eq!(
+            contents.attachments[0].name.as_deref(),
+            Some("Quarterly report.pdf")
+        );
+        assert_eq!(contents.attachments[0].data, None);
+        assert_eq!(contents.attachments[1].name, None);
+        assert!(decode_tnef(&blob[..blob.len() - 3], false).is_err());
+    }
+}
@@ -0,0 +1,128 @@
+use std::collections::{HashMap, HashSet};
+
+use icu_casemap::CaseMapper;
+use icu_locale_core::LanguageIdentifier;
+use serde::{Deserialize, Serialize};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::normalization::case_fold;
+use crate::CFG;
+
+lazy_static! {
+    /// The configured stopwords, case folded for matching.
+    static ref STOPWORDS: HashMap<String, HashSet<String>> = CFG
+        .stopwords
+        .iter()
+        .map(|(lang, words)| {
+            let folded = words.iter().map(|word| case_fold(word).into_owned()).collect();
+            (lang.to_lowercase(), folded)
+        })
+        .collect();
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct TokenizeOptions {
+    #[serde(default)]
+    pub lowercase: bool,
+
+    /// Full Unicode case folding, for caseless matching. `Straße` and `STRASSE` both become `strasse`.
+    #[serde(default)]
+    pub fold: bool,
+
+    /// Removes the stopwords of the given ISO 639-3 language, as configured in `cfg.toml`.
+    /// `auto` picks the list by the detected language. Ignored for sentences.
+    pub stopwords: Option<String>,
+
+    /// Returns every token along with its UTF-8 byte offsets within the text.
+    #[serde(default)]
+    pub offsets: bool,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(untagged)]
+pub enum Tokens {
+    Plain(Vec<String>),
+    WithOffsets(Vec<Token>),
+}
+
+#[derive(Serialize, Debug)]
+pub struct Token {
+    pub text: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Splits into words along UAX #29 word boundaries, leaving out whitespace and punctuation.
+pub fn words(src: &str, options: &TokenizeOptions) -> Result<Tokens, String> {
+    let stopwords = match options.stopwords.as_deref() {
+        Some(lang) => stopwords_for(src, lang)?,
+        None => None,
+    };
+
+    let words = src.unicode_word_indices().filter(|(_, word)| {
+        stopwords.is_none_or(|stopwords| !stopwords.contains(&*case_fold(word)))
+    });
+
+    Ok(collect_tokens(words, options))
+}
+
+/// Splits into sentences along UAX #29 sentence boundaries, without their trailing whitespace.
+pub fn sentences(src: &str, options: &TokenizeOptions) -> Tokens {
+    let sentences = src
+        .split_sentence_bound_indices()
+        .map(|(offset, sentence)| (offset, sentence.trim_end()))
+        .filter(|(_, sentence)| !sentence.is_empty());
+
+    collect_tokens(sentences, options)
+}
+
+fn collect_tokens<'src>(
+    tokens: impl Iterator<Item = (usize, &'src str)>,
+    options: &TokenizeOptions,
+) -> Tokens {
+    let normalize = |token: &str| -> String {
+        if options.fold {
+            case_fold(token).into_owned()
+        } else if options.lowercase {
+            CaseMapper::new()
+                .lowercase_to_string(token, &LanguageIdentifier::UNKNOWN)
+                .into_owned()
+        } else {
+            token.to_owned()
+        }
+    };
+
+    if options.offsets {
+        Tokens::WithOffsets(
+            tokens
+                .map(|(start, token)| Token {
+                    text: normalize(token),
+                    start,
+                    end: start + token.len(),
+                })
+                .collect(),
+        )
+    } else {
+        Tokens::Plain(tokens.map(|(_, token)| normalize(token)).collect())
+    }
+}
+
+/// An `auto` language without a configured list means there's nothing to remove.
+fn stopwords_for(src: &str, lang: &str) -> Result<Option<&'static HashSet<String>>, String> {
+    if lang.eq_ignore_ascii_case("auto") {
+        let detected = whatlang::detect_lang(src).map(|lang| lang.code());
+        return Ok(detected.and_then(|code| STOPWORDS.get(code)));
+    }
+
+    let Some(stopwords) = STOPWORDS.get(&lang.to_lowercase()) else {
+        let mut configured: Vec<&str> = STOPWORDS.keys().map(String::as_str).collect();
+        configured.sort_unstable();
+
+        return Err(format!(
+            "No stopwords configured for language: {lang}. Configured: {}",
+            configured.join(", ")
+        ));
+    };
+
+    Ok(Some(stopwords))
+}
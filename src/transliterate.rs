@@ -0,0 +1,643 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// ISO 9:1995 (GOST 7.79-2000 System A). Each letter has a transliteration of its own, so it's reversible.
+const ISO_9: [(char, &str); 48] = [
+    ('а', "a"),
+    ('б', "b"),
+    ('в', "v"),
+    ('г', "g"),
+    ('ґ', "g\u{300}"),
+    ('д', "d"),
+    ('ѓ', "ǵ"),
+    ('ђ', "đ"),
+    ('е', "e"),
+    ('ё', "ë"),
+    ('є', "ê"),
+    ('ж', "ž"),
+    ('з', "z"),
+    ('ѕ', "ẑ"),
+    ('и', "i"),
+    ('і', "ì"),
+    ('ї', "ï"),
+    ('й', "j"),
+    ('ј', "ǰ"),
+    ('к', "k"),
+    ('ќ', "ḱ"),
+    ('л', "l"),
+    ('љ', "l\u{302}"),
+    ('м', "m"),
+    ('н', "n"),
+    ('њ', "n\u{302}"),
+    ('о', "o"),
+    ('п', "p"),
+    ('р', "r"),
+    ('с', "s"),
+    ('т', "t"),
+    ('ћ', "ć"),
+    ('у', "u"),
+    ('ў', "ǔ"),
+    ('ф', "f"),
+    ('х', "h"),
+    ('ц', "c"),
+    ('ч', "č"),
+    ('џ', "d\u{302}"),
+    ('ш', "š"),
+    ('щ', "ŝ"),
+    ('ъ', "ʺ"),
+    ('ы', "y"),
+    ('ь', "ʹ"),
+    ('э', "è"),
+    ('ю', "û"),
+    ('я', "â"),
+    ('ѣ', "ě"),
+];
+
+const GREEK: [(char, &str); 25] = [
+    ('α', "a"),
+    ('β', "v"),
+    ('γ', "g"),
+    ('δ', "d"),
+    ('ε', "e"),
+    ('ζ', "z"),
+    ('η', "i"),
+    ('θ', "th"),
+    ('ι', "i"),
+    ('κ', "k"),
+    ('λ', "l"),
+    ('μ', "m"),
+    ('ν', "n"),
+    ('ξ', "x"),
+    ('ο', "o"),
+    ('π', "p"),
+    ('ρ', "r"),
+    ('σ', "s"),
+    ('ς', "s"),
+    ('τ', "t"),
+    ('υ', "y"),
+    ('φ', "f"),
+    ('χ', "ch"),
+    ('ψ', "ps"),
+    ('ω', "o"),
+];
+
+/// Hiragana in modified Hepburn. Katakana is read through its hiragana counterpart.
+const KANA: [(char, &str); 88] = [
+    ('あ', "a"),
+    ('い', "i"),
+    ('う', "u"),
+    ('え', "e"),
+    ('お', "o"),
+    ('か', "ka"),
+    ('き', "ki"),
+    ('く', "ku"),
+    ('け', "ke"),
+    ('こ', "ko"),
+    ('が', "ga"),
+    ('ぎ', "gi"),
+    ('ぐ', "gu"),
+    ('げ', "ge"),
+    ('ご', "go"),
+    ('さ', "sa"),
+    ('し', "shi"),
+    ('す', "su"),
+    ('せ', "se"),
+    ('そ', "so"),
+    ('ざ', "za"),
+    ('じ', "ji"),
+    ('ず', "zu"),
+    ('ぜ', "ze"),
+    ('ぞ', "zo"),
+    ('た', "ta"),
+    ('ち', "chi"),
+    ('つ', "tsu"),
+    ('て', "te"),
+    ('と', "to"),
+    ('だ', "da"),
+    ('ぢ', "ji"),
+    ('づ', "zu"),
+    ('で', "de"),
+    ('ど', "do"),
+    ('な', "na"),
+    ('に', "ni"),
+    ('ぬ', "nu"),
+    ('ね', "ne"),
+    ('の', "no"),
+    ('は', "ha"),
+    ('ひ', "hi"),
+    ('ふ', "fu"),
+    ('へ', "he"),
+    ('ほ', "ho"),
+    ('ば', "ba"),
+    ('び', "bi"),
+    ('ぶ', "bu"),
+    ('べ', "be"),
+    ('ぼ', "bo"),
+    ('ぱ', "pa"),
+    ('ぴ', "pi"),
+    ('ぷ', "pu"),
+    ('ぺ', "pe"),
+    ('ぽ', "po"),
+    ('ま', "ma"),
+    ('み', "mi"),
+    ('む', "mu"),
+    ('め', "me"),
+    ('も', "mo"),
+    ('や', "ya"),
+    ('ゆ', "yu"),
+    ('よ', "yo"),
+    ('ら', "ra"),
+    ('り', "ri"),
+    ('る', "ru"),
+    ('れ', "re"),
+    ('ろ', "ro"),
+    ('わ', "wa"),
+    ('ゐ', "wi"),
+    ('ゑ', "we"),
+    ('を', "o"),
+    ('ん', "n"),
+    ('ぁ', "a"),
+    ('ぃ', "i"),
+    ('ぅ', "u"),
+    ('ぇ', "e"),
+    ('ぉ', "o"),
+    ('ゃ', "ya"),
+    ('ゅ', "yu"),
+    ('ょ', "yo"),
+    ('ゎ', "wa"),
+    ('ゔ', "vu"),
+    ('ゕ', "ka"),
+    ('ゖ', "ke"),
+    ('。', "."),
+    ('、', ","),
+    ('・', " "),
+];
+
+lazy_static! {
+    static ref ISO_9_REVERSE: HashMap<String, char> = ISO_9
+        .iter()
+        .map(|(cyrillic, latin)| (latin.nfc().collect(), *cyrillic))
+        .collect();
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scheme {
+    /// Any script into plain ASCII, along the lines of `unidecode`.
+    Ascii,
+    CyrillicLatin,
+    LatinCyrillic,
+    HebrewLatin,
+    GreekLatin,
+    KanaRomaji,
+}
+
+impl FromStr for Scheme {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().replace('_', "-").as_str() {
+            "ascii" => Ok(Self::Ascii),
+            "cyrillic-latin" | "iso9" | "gost" => Ok(Self::CyrillicLatin),
+            "latin-cyrillic" => Ok(Self::LatinCyrillic),
+            "hebrew-latin" => Ok(Self::HebrewLatin),
+            "greek-latin" | "elot743" => Ok(Self::GreekLatin),
+            "kana-romaji" | "romaji" | "hepburn" => Ok(Self::KanaRomaji),
+            _ => Err(format!(
+                "Unknown transliteration scheme: {s}. Expected one of: ascii, cyrillic-latin, latin-cyrillic, hebrew-latin, greek-latin, kana-romaji"
+            )),
+        }
+    }
+}
+
+pub fn transliterate(src: &str, scheme: Scheme) -> String {
+    match scheme {
+        Scheme::Ascii => to_ascii(src),
+        Scheme::CyrillicLatin => cyrillic_to_latin(src),
+        Scheme::LatinCyrillic => latin_to_cyrillic(src),
+        Scheme::HebrewLatin => hebrew_to_latin(src),
+        Scheme::GreekLatin => greek_to_latin(src),
+        Scheme::KanaRomaji => kana_to_romaji(src),
+    }
+}
+
+pub fn to_ascii(src: &str) -> String {
+    deunicode::deunicode(src)
+}
+
+fn lookup(table: &'static [(char, &'static str)], c: char) -> Option<&'static str> {
+    table.iter().find(|(from, _)| *from == c).map(|(_, to)| *to)
+}
+
+/// Gives `result` the case of `c`. A capital followed by another capital is taken to be
+/// a part of an all caps word, so `Ш` is `Š` before `А`, while `Щ` is `Ŝ` either way.
+fn with_case_of(result: &str, c: char, next: Option<char>) -> String {
+    if !c.is_uppercase() {
+        return result.to_owned();
+    }
+
+    if next.is_some_and(char::is_uppercase) || result.chars().count() == 1 {
+        return result.to_uppercase();
+    }
+
+    let mut chars = result.chars();
+    chars.next().map_or_else(String::new, |first| {
+        first.to_uppercase().chain(chars).collect()
+    })
+}
+
+pub fn cyrillic_to_latin(src: &str) -> String {
+    let chars: Vec<char> = src.chars().collect();
+
+    chars
+        .iter()
+        .enumerate()
+        .map(|(idx, &c)| {
+            let lower = c.to_lowercase().next().unwrap_or(c);
+
+            match lookup(&ISO_9, lower) {
+                Some(latin) => with_case_of(latin, c, chars.get(idx + 1).copied()),
+                None => c.to_string(),
+            }
+        })
+        .collect::<String>()
+        .nfc()
+        .collect()
+}
+
+/// Reverses ISO 9. Letters outside of it, such as `w` or `q`, are kept as they are.
+pub fn latin_to_cyrillic(src: &str) -> String {
+    let chars: Vec<char> = src.nfc().collect();
+    let mut result = String::with_capacity(src.len());
+    let mut idx = 0;
+
+    while idx < chars.len() {
+        let c = chars[idx];
+
+        // Some letters are written with a combining mark, which takes a character of its own.
+        let matched = [2, 1].into_iter().find_map(|len| {
+            let candidate: String = chars.get(idx..idx + len)?.iter().collect();
+            let lower: String = candidate.to_lowercase();
+            ISO_9_REVERSE.get(&lower).map(|cyrillic| (*cyrillic, len))
+        });
+
+        let Some((cyrillic, len)) = matched else {
+            result.push(c);
+            idx += 1;
+            continue;
+        };
+
+        if c.is_uppercase() {
+            result.extend(cyrillic.to_uppercase());
+        } else {
+            result.push(cyrillic);
+        }
+
+        idx += len;
+    }
+
+    result
+}
+
+/// Reads Hebrew letters along with their niqqud, when present.
+/// Unpointed text has no vowels to go by, so ו and י are read as vowels inside a word,
+/// and ב, כ and פ are read as `b`, `k` and `p` unless they're final.
+pub fn hebrew_to_latin(src: &str) -> String {
+    const DAGESH: char = '\u{05BC}';
+    const SIN_DOT: char = '\u{05C2}';
+    const GERESH: [char; 2] = ['\u{05F3}', '\''];
+
+    let graphemes: Vec<&str> = src.graphemes(true).collect();
+    let is_pointed = src
+        .chars()
+        .any(|c| hebrew_vowel(c).is_some() || c == DAGESH);
+
+    let mut result = String::with_capacity(src.len());
+    let mut prev_vowel: Option<&str> = None;
+    let mut idx = 0;
+
+    while idx < graphemes.len() {
+        let grapheme = graphemes[idx];
+        let mut chars = grapheme.chars();
+        let letter = chars.next().unwrap_or_default();
+        let marks: Vec<char> = chars.collect();
+
+        let has_dagesh = marks.contains(&DAGESH);
+
+        // A geresh follows the letter it modifies, so it's a part of the word.
+        let prev_is_letter = idx > 0 && {
+            let prev = first_char(graphemes[idx - 1]);
+            is_hebrew_letter(prev) || GERESH.contains(&prev)
+        };
+        let next = graphemes.get(idx + 1).map(|g| first_char(g));
+        // Only these letters take a geresh. After any other letter it's an apostrophe, and kept.
+        let has_geresh = matches!(letter, 'ג' | 'ז' | 'צ' | 'ץ' | 'ת')
+            && next.is_some_and(|next| GERESH.contains(&next));
+
+        let vowel = marks.iter().find_map(|mark| hebrew_vowel(*mark));
+
+        let consonant = match letter {
+            'ג' if has_geresh => "j",
+            'ז' if has_geresh => "zh",
+            'צ' | 'ץ' if has_geresh => "ch",
+            'ת' if has_geresh => "th",
+            'א' | 'ע' => "",
+            'ב' if has_dagesh || !is_pointed => "b",
+            'ג' => "g",
+            'ד' => "d",
+            'ה' => "h",
+            'ו' if marks.contains(&'\u{05B9}') || marks.contains(&'\u{05BA}') => "o",
+            'ו' if has_dagesh && vowel.is_none() => "u",
+            'ו' if !is_pointed && prev_is_letter && next != Some('ו') => "o",
+            'ב' | 'ו' => "v",
+            'ז' => "z",
+            'ח' => "ch",
+            'ט' | 'ת' => "t",
+            'י' if !is_pointed && prev_is_letter => "i",
+            // A silent mater lectionis, lengthening the vowel before it.
+            'י' if marks.is_empty() && matches!(prev_vowel, Some("i" | "e")) => "",
+            'י' => "y",
+            'כ' if has_dagesh || !is_pointed => "k",
+            'כ' | 'ך' => "kh",
+            'ל' => "l",
+            'מ' | 'ם' => "m",
+            'נ' | 'ן' => "n",
+            'ס' => "s",
+            'פ' if has_dagesh || !is_pointed => "p",
+            'פ' | 'ף' => "f",
+            'צ' | 'ץ' => "ts",
+            'ק' => "k",
+            'ר' => "r",
+            'ש' if marks.contains(&SIN_DOT) => "s",
+            'ש' => "sh",
+            '\u{05BE}' => "-",
+            '\u{05C3}' => ".",
+            _ => {
+                result.push_str(grapheme);
+                prev_vowel = None;
+                idx += 1;
+                continue;
+            }
+        };
+
+        result.push_str(consonant);
+
+        // A holam or a shuruk makes the ו a vowel of its own.
+        if letter == 'ו' && matches!(consonant, "o" | "u") {
+            prev_vowel = Some(consonant);
+        } else {
+            result.push_str(vowel.unwrap_or_default());
+            prev_vowel = vowel;
+        }
+
+        idx += if has_geresh { 2 } else { 1 };
+    }
+
+    result
+}
+
+fn first_char(src: &str) -> char {
+    src.chars().next().unwrap_or_default()
+}
+
+fn is_hebrew_letter(c: char) -> bool {
+    ('\u{05D0}'..='\u{05EA}').contains(&c)
+}
+
+fn hebrew_vowel(mark: char) -> Option<&'static str> {
+    match mark {
+        '\u{05B1}' | '\u{05B5}' | '\u{05B6}' => Some("e"),
+        '\u{05B2}' | '\u{05B7}' | '\u{05B8}' => Some("a"),
+        '\u{05B3}' | '\u{05B9}' | '\u{05BA}' | '\u{05C7}' => Some("o"),
+        '\u{05B4}' => Some("i"),
+        '\u{05BB}' => Some("u"),
+        _ => None,
+    }
+}
+
+/// ELOT 743 (ISO 843 transcription). The accents of Greek letters are dropped, while a diaeresis
+/// keeps the vowels it separates from being read as a pair. Marks on anything else are kept.
+pub fn greek_to_latin(src: &str) -> String {
+    const DIAERESIS: char = '\u{0308}';
+    // Tonos, grave, perispomeni, psili, dasia and ypogegrammeni, as Greek letters decompose into.
+    const ACCENTS: [char; 6] = [
+        '\u{0301}', '\u{0300}', '\u{0342}', '\u{0313}', '\u{0314}', '\u{0345}',
+    ];
+
+    let is_greek = |c: char| {
+        c.to_lowercase()
+            .next()
+            .is_some_and(|c| lookup(&GREEK, c).is_some())
+    };
+
+    // Each letter along with whether it carries a diaeresis. Marks on other letters are kept
+    // as letters of their own, and recomposed at the end.
+    let mut letters: Vec<(char, bool)> = Vec::with_capacity(src.len());
+
+    for c in src.nfd() {
+        match letters.last_mut() {
+            Some((last, diaeresis)) if is_greek(*last) && c == DIAERESIS => *diaeresis = true,
+            Some((last, _)) if is_greek(*last) && ACCENTS.contains(&c) => {}
+            _ => letters.push((c, false)),
+        }
+    }
+
+    let lower = |idx: usize| {
+        letters
+            .get(idx)
+            .map(|(c, diaeresis)| (c.to_lowercase().next().unwrap_or(*c), *diaeresis))
+    };
+    let is_greek_letter = |idx: usize| lower(idx).is_some_and(|(c, _)| lookup(&GREEK, c).is_some());
+
+    let mut result = String::with_capacity(src.len());
+    let mut idx = 0;
+
+    while idx < letters.len() {
+        let (c, _) = letters[idx];
+        let (lower_c, _) = lower(idx).unwrap_or_default();
+        let next = lower(idx + 1);
+        let next_char = next.map(|(c, _)| c);
+        let next_is_pair = next.is_some_and(|(_, diaeresis)| !diaeresis);
+
+        let (latin, len): (Cow<str>, usize) = match (lower_c, next_char) {
+            ('ο', Some('υ')) if next_is_pair => ("ou".into(), 2),
+            ('α' | 'ε' | 'η', Some('υ')) if next_is_pair => {
+                let after = lower(idx + 2).map(|(c, _)| c);
+                let is_voiced = after.is_some_and(|c| "αεηιουωβγδζλμνρ".contains(c));
+                let first = lookup(&GREEK, lower_c).unwrap_or_default();
+                let second = if is_voiced { "v" } else { "f" };
+                (format!("{first}{second}").into(), 2)
+            }
+            ('γ', Some('γ')) => ("ng".into(), 2),
+            ('γ', Some('ξ')) => ("nx".into(), 2),
+            ('γ', Some('χ')) => ("nch".into(), 2),
+            // `μπ` is `b` at the edges of a word, and `mp` within it.
+            ('μ', Some('π'))
+                if !is_greek_letter(idx.wrapping_sub(1)) || !is_greek_letter(idx + 2) =>
+            {
+                ("b".into(), 2)
+            }
+            _ => match lookup(&GREEK, lower_c) {
+                Some(latin) => (latin.into(), 1),
+                None => (c.to_string().into(), 1),
+            },
+        };
+
+        let next_letter = letters.get(idx + 1).map(|(c, _)| *c);
+        result.push_str(&with_case_of(&latin, c, next_letter));
+
+        idx += len;
+    }
+
+    result.nfc().collect()
+}
+
+/// Modified Hepburn, writing long vowels out rather than with a macron, so the result is plain ASCII.
+pub fn kana_to_romaji(src: &str) -> String {
+    const SOKUON: char = 'っ';
+    const CHOONPU: char = 'ー';
+
+    let kana: Vec<char> = src.chars().map(katakana_to_hiragana).collect();
+    let mut result = String::with_capacity(src.len());
+    let mut double_next = false;
+    let mut idx = 0;
+
+    while idx < kana.len() {
+        let c = kana[idx];
+
+        if c == SOKUON {
+            double_next = true;
+            idx += 1;
+            continue;
+        }
+
+        if c == CHOONPU {
+            if let Some(vowel) = result.chars().last().filter(|c| "aeiou".contains(*c)) {
+                result.push(vowel);
+            }
+            idx += 1;
+            continue;
+        }
+
+        let Some(base) = lookup(&KANA, c) else {
+            result.push(c);
+            double_next = false;
+            idx += 1;
+            continue;
+        };
+
+        let mut romaji = base.to_owned();
+        idx += 1;
+
+        // Small kana combine with the one before them: `きゃ` is `kya`, `しゃ` is `sha` and `ファ` is `fa`.
+        if let Some(small) = kana.get(idx).and_then(|next| small_kana(*next)) {
+            if romaji.len() > 1 || romaji == "u" {
+                romaji.pop();
+
+                if romaji.is_empty() {
+                    romaji.push('w');
+                }
+
+                let is_palatal = romaji.ends_with("sh") || romaji.ends_with("ch") || romaji == "j";
+                romaji.push_str(if is_palatal {
+                    small.trim_start_matches('y')
+                } else {
+                    small
+                });
+                idx += 1;
+            }
+        }
+
+        if double_next {
+            match romaji.strip_prefix("ch") {
+                Some(_) => result.push('t'),
+                None => result.extend(romaji.chars().next().filter(|c| !"aeiou".contains(*c))),
+            }
+            double_next = false;
+        }
+
+        // `ん` is written `n'` where it would otherwise be read as a part of the next syllable.
+        if romaji == "n" {
+            let next = kana.get(idx).and_then(|next| lookup(&KANA, *next));
+            if next.is_some_and(|next| next.starts_with(['a', 'e', 'i', 'o', 'u', 'y'])) {
+                romaji.push('\'');
+            }
+        }
+
+        result.push_str(&romaji);
+    }
+
+    result
+}
+
+fn katakana_to_hiragana(c: char) -> char {
+    match c {
+        'ァ'..='ヶ' => char::from_u32(u32::from(c) - 0x60).unwrap_or(c),
+        _ => c,
+    }
+}
+
+fn small_kana(c: char) -> Option<&'static str> {
+    match c {
+        'ゃ' => Some("ya"),
+        'ゅ' => Some("yu"),
+        'ょ' => Some("yo"),
+        'ぁ' => Some("a"),
+        'ぃ' => Some("i"),
+        'ぅ' => Some("u"),
+        'ぇ' => Some("e"),
+        'ぉ' => Some("o"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn hebrew_geresh_modifies_the_letters_which_take_it() {
+        assert_eq!(hebrew_to_latin("ג׳ירפה"), "jirph");
+        assert_eq!(hebrew_to_latin("ז'קט"), "zhkt");
+        assert_eq!(hebrew_to_latin("צ'יפס"), "chips");
+        assert_eq!(hebrew_to_latin("ת'"), "th");
+    }
+
+    #[test]
+    fn hebrew_apostrophe_is_kept_after_other_letters() {
+        assert_eq!(hebrew_to_latin("ד'"), "d'");
+        assert_eq!(hebrew_to_latin("ה׳"), "h׳");
+        assert_eq!(hebrew_to_latin("ד' ה'"), "d' h'");
+    }
+
+    #[test]
+    fn hebrew_pointed_text() {
+        assert_eq!(hebrew_to_latin("שָׁלוֹם"), "shalom");
+    }
+
+    #[test]
+    fn small_ka_and_ke() {
+        assert_eq!(kana_to_romaji("ヶ月"), "ke月");
+        assert_eq!(kana_to_romaji("ヵ"), "ka");
+        assert_eq!(kana_to_romaji("カタカナ"), "katakana");
+    }
+
+    #[test]
+    fn greek_accents_are_dropped() {
+        assert_eq!(greek_to_latin("Αθήνα"), "Athina");
+        assert_eq!(greek_to_latin("ἀρχή"), "archi");
+        assert_eq!(greek_to_latin("Ευρώπη"), "Evropi");
+        // The diaeresis keeps `αΰ` from being read as a pair.
+        assert_eq!(greek_to_latin("Ταΰγετος"), "Taygetos");
+    }
+
+    #[test]
+    fn marks_on_other_letters_are_kept() {
+        assert_eq!(greek_to_latin("café Καφές"), "café Kafes");
+        assert_eq!(greek_to_latin("ñ"), "ñ");
+        assert_eq!(greek_to_latin("Москва́"), "Москва́");
+    }
+}
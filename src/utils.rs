@@ -7,11 +7,15 @@ use std::borrow::Cow;
 use std::char;
 use std::collections::VecDeque;
 // use std::error::Error;
-use std::{collections::HashMap, usize};
+use std::collections::HashMap;
 
 // use std::string::FromUtf8Error;
 use encoding::{all, DecoderTrap, Encoding};
 
+use crate::codecs;
+use crate::html;
+use crate::language;
+use crate::textops;
 use crate::CFG;
 
 // Unescape code was borrowed from: https://github.com/saghm/unescape-rs.
@@ -44,8 +48,10 @@ pub type DecodingResult<'a> = Result<Cow<'a, str>, DecodingError>;
 
 // pub type UTF8Result = Result<UTF8String, FromUtf8Error>;
 
+/// Reverses by grapheme cluster. See `textops::reverse`.
+#[inline]
 pub fn reverse_str(src: &str) -> String {
-    src.chars().rev().collect()
+    textops::reverse(src)
 }
 
 pub fn to_utf8_lossy(src: &[u8]) -> Cow<'_, str> {
@@ -55,7 +61,7 @@ pub fn to_utf8_lossy(src: &[u8]) -> Cow<'_, str> {
     }
 }
 
-pub fn to_utf8(src: &[u8]) -> DecodingResult {
+pub fn to_utf8(src: &[u8]) -> DecodingResult<'_> {
     let src_as_utf8: &str = std::str::from_utf8(src)?;
     Ok(Cow::Borrowed(src_as_utf8))
 }
@@ -75,12 +81,32 @@ impl From<Cow<'static, str>> for DecodingError {
     }
 }
 
+impl From<&'static str> for DecodingError {
+    fn from(e: &'static str) -> Self {
+        DecodingError(Cow::Borrowed(e))
+    }
+}
+
+impl From<String> for DecodingError {
+    fn from(e: String) -> Self {
+        DecodingError(Cow::Owned(e))
+    }
+}
+
+impl std::fmt::Display for DecodingError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for DecodingError {}
+
 pub trait Reverse {
     fn reverse(&self) -> String;
 }
 
 pub trait DecodeUTF8 {
-    fn decode(&self, encoding: &str, trap: DecoderTrap) -> DecodingResult;
+    fn decode(&self, encoding: &str, trap: DecoderTrap) -> DecodingResult<'_>;
 }
 
 pub trait AsUTF8Lossy {
@@ -95,12 +121,12 @@ impl AsUTF8Lossy for &[u8] {
 }
 
 pub trait AsUTF8 {
-    fn as_utf8(&self) -> DecodingResult;
+    fn as_utf8(&self) -> DecodingResult<'_>;
 }
 
 impl AsUTF8 for &[u8] {
     #[inline]
-    fn as_utf8(&self) -> DecodingResult {
+    fn as_utf8(&self) -> DecodingResult<'_> {
         to_utf8(self)
     }
 }
@@ -114,11 +140,12 @@ impl Reverse for str {
 
 impl DecodeUTF8 for &[u8] {
     #[inline]
-    fn decode(&self, encoding: &str, trap: DecoderTrap) -> DecodingResult {
+    fn decode(&self, encoding: &str, trap: DecoderTrap) -> DecodingResult<'_> {
         decode_bytes(self, encoding, trap)
     }
 }
 
+#[allow(clippy::too_many_lines, clippy::match_same_arms)]
 pub fn decode_bytes<'src, 'encoder>(
     src: &'src [u8],
     encoding: &'encoder str,
@@ -328,7 +355,7 @@ pub fn unescape(s: &str) -> Option<String> {
             Some('x') => s.push(try_option!(unescape_byte(&mut queue))),
             Some(c) if c.is_digit(8) => s.push(try_option!(unescape_octal(c, &mut queue))),
             _ => return None,
-        };
+        }
     }
 
     Some(s)
@@ -358,7 +385,7 @@ pub fn unescape_as_bytes(s: &str) -> Option<Vec<u8>> {
             Some('x') => s.push(try_option!(unescape_byte(&mut queue)) as u8),
             Some(c) if c.is_digit(8) => s.push(try_option!(unescape_octal(c, &mut queue)) as u8),
             _ => return None, // TODO: Return Error: Bad Encoding `\` was combined with an illegal char.
-        };
+        }
     }
 
     Some(s)
@@ -404,7 +431,7 @@ fn unescape_octal_leading(c: char, queue: &VecDeque<char>) -> Option<char> {
 
     let mut s = String::new();
     s.push(c);
-    s.push(*try_option!(queue.get(0)));
+    s.push(*try_option!(queue.front()));
     s.push(*try_option!(queue.get(1)));
 
     let u = try_option!(u32::from_str_radix(&s, 8).ok());
@@ -421,13 +448,19 @@ fn unescape_octal_no_leading(c: char, queue: &mut VecDeque<char>) -> Option<char
 }
 
 /// Attempt to decode given `src` bytes slice into a given encoding format.
+/// A comma separated list of encodings, e.g. `windows-1251,koi8-r`, picks the one which decodes into the most plausible text.
 /// If fails, attempt to use alternative encoding `fallback_encoding` from `cfg.toml`.
 /// If that fails, return a lossy UTF-8.
 /// TODO: Replace `DecodingResult` with `String` or `Cow<'_, str>`; This function cannot fail.
-pub fn attempt_decode<'src, 'encoding>(
-    src: &'src [u8],
-    encoding: &'encoding str,
-) -> DecodingResult<'src> {
+#[allow(clippy::unnecessary_wraps)]
+pub fn attempt_decode<'src>(src: &'src [u8], encoding: &str) -> DecodingResult<'src> {
+    if encoding.contains(',') {
+        let candidates: Vec<&str> = encoding.split(',').map(str::trim).collect();
+        let encoding = language::pick_charset(src, &candidates).unwrap_or(candidates[0]);
+
+        return attempt_decode(src, encoding);
+    }
+
     Ok(match decode_bytes(src, encoding, DEFAULT_DECODER_TRAP) {
         Ok(result) => result,
         // Err(_) => match decode_bytes(src, &CFG.common.alt_encoding, DEFAULT_DECODER_TRAP) {
@@ -438,39 +471,25 @@ pub fn attempt_decode<'src, 'encoding>(
     })
 }
 
-/// Converts escaped special characters to their original characters.
-/// e.g. `\\t` will be converted to `\t`, `\\n` will be converted to `\n` etc.
-#[inline]
-pub fn normalize_str(string: &str) -> Cow<'_, str> {
-    // This is beyond ridiculous -- haven't found a better way yet.
-    // TODO: Find a solution (maybe with tuples?) to replace only what's needed, using Cow
-    let special_symbols = [r"\\", r"\n", r"\r", r"\t", r"\=", r" =?", r"?= "];
-    for symbol in special_symbols {
-        if string.contains(symbol) {
-            return Cow::Owned(
-                string
-                    .replace(r"\\", "\\")
-                    .replace(r"\n", "\n")
-                    .replace(r"\r", "\r")
-                    .replace(r"\t", "\t")
-                    .replace(r"\=", "=")
-                    .replace(" =?", " \r\n=?")
-                    .replace("?= ", "?=\r\n "),
-            );
-        };
-    }
-    Cow::Borrowed(string)
+/// Puts every RFC 2047 encoded word on a line of its own, which is how `decode_mime_header` finds them.
+fn split_encoded_words(src: &str) -> Cow<'_, str> {
+    if src.contains(" =?") || src.contains("?= ") {
+        Cow::Owned(src.replace(" =?", " \r\n=?").replace("?= ", "?=\r\n "))
+    } else {
+        Cow::Borrowed(src)
+    }
 }
 
 // pub fn decode_mime_header(src: &str) -> Cow<'_, str> {
-pub fn decode_mime_header(src: &str) -> DecodingResult {
+#[allow(clippy::unnecessary_wraps)]
+pub fn decode_mime_header(src: &str) -> DecodingResult<'_> {
     if !src.contains("=?") && !src.contains("\\x") && !src.contains("\\u") {
         Ok(Cow::Borrowed(src))
     } else {
         // Decoding usually means the decoded data is smaller or about the same in size as it does not include any MIME header special symbols.
         let mut result = String::with_capacity(src.len());
 
-        for line in src.lines() {
+        for line in split_encoded_words(src).lines() {
             let trimmed_line = line.trim_start();
 
             if trimmed_line.starts_with("=?") && trimmed_line.ends_with("?=") {
@@ -504,16 +523,32 @@ pub fn decode_mime_header(src: &str) -> DecodingResult {
     // }
 }
 
+/// Decodes RFC 2047 encoded words found anywhere within `src`, keeping the text around them.
+/// Unlike `decode_mime_header`, the encoded words don't have to sit on lines of their own.
+/// Non-ASCII input is returned as is, since it's already decoded and the header parser would read it as Latin-1.
+pub fn decode_mime_words(src: &str) -> Cow<'_, str> {
+    if !src.contains("=?") || !src.is_ascii() {
+        return Cow::Borrowed(src);
+    }
+
+    let prefixed_src = format!(":{src}");
+
+    match mailparse::parse_header(prefixed_src.as_bytes()) {
+        Ok((parsed, _)) => Cow::Owned(parsed.get_value()),
+        Err(_) => Cow::Borrowed(src),
+    }
+}
+
 // pub fn decode_quoted_printable(src: String, charset: &str) -> String {
-pub fn decode_quoted_printable<'src, 'charset>(
+pub fn decode_quoted_printable<'src>(
     src: &'src str,
-    charset: &'charset str,
+    charset: &str,
     // ) -> Cow<'src, str>
 ) -> DecodingResult<'src>
 // where
 //     'charset: 'src,
 {
-    match quoted_printable::decode(&src, quoted_printable::ParseMode::Robust) {
+    match quoted_printable::decode(src, quoted_printable::ParseMode::Robust) {
         Ok(v) => Ok(Cow::Owned(attempt_decode(&v, charset)?.into_owned())),
         Err(_) => Ok(Cow::Borrowed(src)),
     }
@@ -521,17 +556,35 @@ pub fn decode_quoted_printable<'src, 'charset>(
 
 // pub fn auto_decode(src: String, charset: &str) -> String {
 // pub fn auto_decode<'src, 'charset>(src: &'src str, charset: &'charset str) -> Cow<'src, str> {
-pub fn auto_decode<'src, 'charset>(src: &'src str, charset: &'charset str) -> DecodingResult<'src> {
-    let src_normalized = normalize_str(src);
+pub fn auto_decode<'src>(src: &'src str, charset: &str) -> DecodingResult<'src> {
+    let src_upper = src.to_uppercase();
 
-    let src_normalized_upper = src_normalized.to_uppercase();
+    if codecs::is_yencoded(src) {
+        let decoded_file = codecs::decode_yenc(src.as_bytes())?;
 
-    if src_normalized_upper.contains("?Q?") || src_normalized_upper.contains("?B?") {
         Ok(Cow::Owned(
-            decode_mime_header(&src_normalized)?.into_owned(), // TODO: It kinda beats the purpose for Cow. Consider using Rc/Arc/Box for Owned values.
+            attempt_decode(&decoded_file.data, charset)?.into_owned(),
+        ))
+    } else if codecs::is_uuencoded(src) {
+        let decoded_file = codecs::decode_uuencode(src)?;
+
+        Ok(Cow::Owned(
+            attempt_decode(&decoded_file.data, charset)?.into_owned(),
+        ))
+    } else if codecs::is_binhexed(src) {
+        let decoded_file = codecs::decode_binhex(src)?;
+
+        Ok(Cow::Owned(
+            attempt_decode(&decoded_file.data, charset)?.into_owned(),
+        ))
+    } else if src_upper.contains("?Q?") || src_upper.contains("?B?") {
+        Ok(Cow::Owned(
+            decode_mime_header(src)?.into_owned(), // TODO: It kinda beats the purpose for Cow. Consider using Rc/Arc/Box for Owned values.
         ))
-    } else if src_normalized.contains("\\x") || src_normalized.contains("\\u") {
-        let unescaped_bytes = unescape_as_bytes(&src_normalized).unwrap();
+    } else if html::contains_entities(src) {
+        Ok(Cow::Owned(html::decode_entities(src).into_owned()))
+    } else if src.contains("\\x") || src.contains("\\u") {
+        let unescaped_bytes = unescape_as_bytes(src).unwrap();
 
         Ok(Cow::Owned(
             attempt_decode(&unescaped_bytes, charset)?.into_owned(),
@@ -541,6 +594,26 @@ pub fn auto_decode<'src, 'charset>(src: &'src str, charset: &'charset str) -> De
     }
 }
 
+/// Same as `auto_decode`, for a source which may not be valid UTF-8, such as yEnc's 8-bit data.
+/// Any other non UTF-8 source is decoded with the given charset before detection takes place.
+pub fn auto_decode_bytes<'src>(src: &'src [u8], charset: &str) -> DecodingResult<'src> {
+    if let Ok(src) = std::str::from_utf8(src) {
+        return auto_decode(src, charset);
+    }
+
+    let src_decoded = attempt_decode(src, charset)?;
+
+    if codecs::is_yencoded(&src_decoded) {
+        let decoded_file = codecs::decode_yenc(src)?;
+
+        Ok(Cow::Owned(
+            attempt_decode(&decoded_file.data, charset)?.into_owned(),
+        ))
+    } else {
+        Ok(Cow::Owned(auto_decode(&src_decoded, charset)?.into_owned()))
+    }
+}
+
 enum MimeEncoding {
     Base64Encoding,
     QEncoding,
@@ -0,0 +1,218 @@
+use std::borrow::Cow;
+use std::num::NonZeroUsize;
+
+use regex::Regex;
+use serde::Deserialize;
+use unicode_width::UnicodeWidthChar;
+
+lazy_static! {
+    static ref FOLDED_LINE_PATTERN: Regex = Regex::new(r"(?:\r\n|\n|\r)([ \t])").unwrap();
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    Lf,
+    Crlf,
+    Cr,
+}
+
+impl LineEnding {
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Lf => "\n",
+            Self::Crlf => "\r\n",
+            Self::Cr => "\r",
+        }
+    }
+}
+
+/// Line and whitespace normalization, accepted as query parameters by the decoding endpoints.
+/// Everything is off unless requested.
+#[derive(Deserialize, Debug, Default)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct WhitespaceOptions {
+    /// Turns literal `\n`, `\r`, `\t`, `\\` and `\=` sequences into what they stand for,
+    /// as in headers copied out of logs or source code. Applied to the input of the header decoders.
+    #[serde(default)]
+    pub unescape_literals: bool,
+
+    /// Unfolds RFC 5322 headers, joining each line which starts with whitespace to the one before it.
+    /// Applied to the input of the header decoders.
+    #[serde(default)]
+    pub unfold: bool,
+
+    /// Converts every line ending of the output.
+    pub line_endings: Option<LineEnding>,
+
+    /// Expands tabs into spaces, up to the next tab stop of the given width.
+    /// A width of 0 is rejected by the query extractor, rather than deleting every tab.
+    pub expand_tabs: Option<NonZeroUsize>,
+
+    /// Strips whitespace at the end of every line of the output.
+    #[serde(default)]
+    pub trim_trailing: bool,
+
+    /// Ends non-empty output with a line ending.
+    #[serde(default)]
+    pub final_newline: bool,
+}
+
+impl WhitespaceOptions {
+    fn changes_output(&self) -> bool {
+        self.line_endings.is_some()
+            || self.expand_tabs.is_some()
+            || self.trim_trailing
+            || self.final_newline
+    }
+}
+
+/// Applies the options which concern the input, before it's decoded.
+pub fn prepare<'src>(src: &'src str, options: &WhitespaceOptions) -> Cow<'src, str> {
+    let mut result = Cow::Borrowed(src);
+
+    if options.unescape_literals {
+        result = Cow::Owned(unescape_literals(&result).into_owned());
+    }
+
+    if options.unfold {
+        result = Cow::Owned(unfold(&result).into_owned());
+    }
+
+    result
+}
+
+/// Applies the options which concern the output, after it was decoded.
+pub fn finish<'src>(src: &'src str, options: &WhitespaceOptions) -> Cow<'src, str> {
+    if !options.changes_output() {
+        return Cow::Borrowed(src);
+    }
+
+    let mut result = String::with_capacity(src.len());
+    let mut last_ending: &str = "";
+
+    for (line, ending) in split_lines(src) {
+        let line = match options.expand_tabs {
+            Some(tab_width) => Cow::Owned(expand_tabs(line, tab_width)),
+            None => Cow::Borrowed(line),
+        };
+
+        if options.trim_trailing {
+            result.push_str(line.trim_end());
+        } else {
+            result.push_str(&line);
+        }
+
+        if !ending.is_empty() {
+            let ending = match options.line_endings {
+                Some(line_ending) => line_ending.as_str(),
+                None => ending,
+            };
+            result.push_str(ending);
+            last_ending = ending;
+        }
+    }
+
+    if options.final_newline && !result.is_empty() && !result.ends_with(['\n', '\r']) {
+        // Matches the line endings already in use, when none were requested.
+        let ending = match (options.line_endings, last_ending) {
+            (Some(line_ending), _) => line_ending.as_str(),
+            (None, "") => "\n",
+            (None, ending) => ending,
+        };
+
+        result.push_str(ending);
+    }
+
+    Cow::Owned(result)
+}
+
+/// Splits into lines along with their line endings. The last line's ending may be empty.
+fn split_lines(src: &str) -> impl Iterator<Item = (&str, &str)> {
+    let mut rest = src;
+
+    std::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+
+        let (line, ending, next) = match rest.find(['\r', '\n']) {
+            Some(idx) => {
+                let ending_len = if rest[idx..].starts_with("\r\n") {
+                    2
+                } else {
+                    1
+                };
+                (
+                    &rest[..idx],
+                    &rest[idx..idx + ending_len],
+                    &rest[idx + ending_len..],
+                )
+            }
+            None => (rest, "", ""),
+        };
+
+        rest = next;
+        Some((line, ending))
+    })
+}
+
+fn expand_tabs(line: &str, tab_width: NonZeroUsize) -> String {
+    let tab_width = tab_width.get();
+    let mut result = String::with_capacity(line.len());
+    let mut column = 0;
+
+    for c in line.chars() {
+        if c == '\t' {
+            let spaces = tab_width - column % tab_width;
+
+            result.extend(std::iter::repeat_n(' ', spaces));
+            column += spaces;
+        } else {
+            result.push(c);
+            column += c.width().unwrap_or_default();
+        }
+    }
+
+    result
+}
+
+pub fn unfold(src: &str) -> Cow<'_, str> {
+    FOLDED_LINE_PATTERN.replace_all(src, "$1")
+}
+
+/// Replaces literal escape sequences in a single pass, so `\\n` stays a backslash followed by `n`.
+pub fn unescape_literals(src: &str) -> Cow<'_, str> {
+    if !src.contains('\\') {
+        return Cow::Borrowed(src);
+    }
+
+    let mut result = String::with_capacity(src.len());
+    let mut chars = src.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+
+        let unescaped = match chars.peek() {
+            Some('\\') => Some('\\'),
+            Some('n') => Some('\n'),
+            Some('r') => Some('\r'),
+            Some('t') => Some('\t'),
+            Some('=') => Some('='),
+            _ => None,
+        };
+
+        match unescaped {
+            Some(unescaped) => {
+                result.push(unescaped);
+                chars.next();
+            }
+            None => result.push(c),
+        }
+    }
+
+    Cow::Owned(result)
+}